@@ -0,0 +1,241 @@
+use proc_macro2::{Literal, TokenStream};
+use quote::{format_ident, quote};
+use syn::ext::IdentExt;
+use syn::{parse_quote, Attribute, Data, DeriveInput, Fields, Generics, Ident, LitStr};
+
+pub fn into_value(input: DeriveInput) -> syn::Result<TokenStream> {
+    let name = &input.ident;
+    let generics = bound(&input.generics, quote!(::askql_parser::IntoValue));
+    let (impl_generics, type_generics, where_clause) = generics.split_for_impl();
+    let body = match &input.data {
+        Data::Struct(data) => {
+            let (pattern, value) = fields_into_value(&data.fields)?;
+            quote!(let #name #pattern = self; #value)
+        }
+        Data::Enum(data) => {
+            let mut arms = Vec::new();
+            for variant in &data.variants {
+                let variant_name = &variant.ident;
+                let key = key(&variant.attrs, variant_name)?;
+                arms.push(if let Fields::Unit = variant.fields {
+                    quote!(#name::#variant_name => ::askql_parser::Value::String(
+                        ::std::string::String::from(#key)
+                    ))
+                } else {
+                    let (pattern, value) = fields_into_value(&variant.fields)?;
+                    quote!(#name::#variant_name #pattern => ::askql_parser::Value::Object(
+                        ::std::iter::Iterator::collect(::std::iter::once(
+                            (::std::string::String::from(#key), #value)
+                        ))
+                    ))
+                });
+            }
+            quote!(match self { #(#arms,)* })
+        }
+        Data::Union(_) => {
+            return Err(syn::Error::new_spanned(
+                &input.ident,
+                "IntoValue cannot be derived for unions",
+            ))
+        }
+    };
+    Ok(quote! {
+        impl #impl_generics ::askql_parser::IntoValue for #name #type_generics #where_clause {
+            fn into_value(self) -> ::askql_parser::Value {
+                #body
+            }
+        }
+    })
+}
+
+pub fn from_value(input: DeriveInput) -> syn::Result<TokenStream> {
+    let name = &input.ident;
+    let generics = bound(&input.generics, quote!(::askql_parser::FromValue));
+    let (impl_generics, type_generics, where_clause) = generics.split_for_impl();
+    let body = match &input.data {
+        Data::Struct(data) => fields_from_value(quote!(#name), &data.fields, &name.to_string())?,
+        Data::Enum(data) => {
+            let expected = format!("a variant of {}", name);
+            let mut units = Vec::new();
+            let mut others = Vec::new();
+            for variant in &data.variants {
+                let variant_name = &variant.ident;
+                let key = key(&variant.attrs, variant_name)?;
+                if let Fields::Unit = variant.fields {
+                    units.push(quote!(#key => ::std::result::Result::Ok(#name::#variant_name)));
+                    continue;
+                }
+                let construct = fields_from_value(
+                    quote!(#name::#variant_name),
+                    &variant.fields,
+                    &format!("{}::{}", name, variant_name),
+                )?;
+                others.push(
+                    quote!(#key => (|value: ::askql_parser::Value| -> ::std::result::Result<
+                    Self,
+                    ::askql_parser::FromValueError,
+                > { #construct })(value)
+                .map_err(|error| error.at_key(#key))),
+                );
+            }
+            quote! {
+                let unknown = |key: &str| ::askql_parser::FromValueError::new(
+                    ::std::format!("unknown variant `{}` of {}", key, ::std::stringify!(#name))
+                );
+                match value {
+                    ::askql_parser::Value::String(key) => match key.as_str() {
+                        #(#units,)*
+                        _ => ::std::result::Result::Err(unknown(&key)),
+                    },
+                    ::askql_parser::Value::Object(object) if object.len() == 1 => {
+                        let (key, value) = ::std::iter::IntoIterator::into_iter(object).next().unwrap();
+                        match key.as_str() {
+                            #(#others,)*
+                            _ => ::std::result::Result::Err(unknown(&key)),
+                        }
+                    }
+                    other => ::std::result::Result::Err(
+                        ::askql_parser::FromValueError::expected(#expected, &other)
+                    ),
+                }
+            }
+        }
+        Data::Union(_) => {
+            return Err(syn::Error::new_spanned(
+                &input.ident,
+                "FromValue cannot be derived for unions",
+            ))
+        }
+    };
+    Ok(quote! {
+        impl #impl_generics ::askql_parser::FromValue for #name #type_generics #where_clause {
+            fn from_value(
+                value: ::askql_parser::Value,
+            ) -> ::std::result::Result<Self, ::askql_parser::FromValueError> {
+                #body
+            }
+        }
+    })
+}
+
+// Adds `bound` to every type parameter.
+fn bound(generics: &Generics, bound: TokenStream) -> Generics {
+    let mut generics = generics.clone();
+    for param in generics.type_params_mut() {
+        param.bounds.push(parse_quote!(#bound));
+    }
+    generics
+}
+
+// The key a field or variant is written under: its name, or the one given
+// with `#[askql(rename = "...")]`.
+fn key(attrs: &[Attribute], ident: &Ident) -> syn::Result<String> {
+    let mut key = ident.unraw().to_string();
+    for attr in attrs.iter().filter(|attr| attr.path().is_ident("askql")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("rename") {
+                key = meta.value()?.parse::<LitStr>()?.value();
+                Ok(())
+            } else {
+                Err(meta.error("expected `rename`"))
+            }
+        })?;
+    }
+    Ok(key)
+}
+
+// The keys of named fields.
+fn field_keys(fields: &Fields) -> syn::Result<Vec<String>> {
+    fields
+        .iter()
+        .flat_map(|field| field.ident.as_ref().map(|ident| key(&field.attrs, ident)))
+        .collect()
+}
+
+// A pattern binding the fields, and the value built from the bindings.
+fn fields_into_value(fields: &Fields) -> syn::Result<(TokenStream, TokenStream)> {
+    let bindings: Vec<Ident> = (0..fields.len())
+        .map(|index| format_ident!("field{}", index))
+        .collect();
+    Ok(match fields {
+        Fields::Named(named) => {
+            let names: Vec<&Ident> = named.named.iter().flat_map(|f| &f.ident).collect();
+            let keys = field_keys(fields)?;
+            (
+                quote!({ #(#names: #bindings),* }),
+                quote!(::askql_parser::Value::Object(
+                    ::std::iter::Iterator::collect(::std::iter::IntoIterator::into_iter(
+                        ::std::vec![#((
+                        ::std::string::String::from(#keys),
+                        ::askql_parser::IntoValue::into_value(#bindings),
+                    )),*]
+                    ))
+                )),
+            )
+        }
+        Fields::Unnamed(_) if bindings.len() == 1 => (
+            quote!((#(#bindings),*)),
+            quote!(::askql_parser::IntoValue::into_value(field0)),
+        ),
+        Fields::Unnamed(_) => (
+            quote!((#(#bindings),*)),
+            quote!(::askql_parser::Value::List(::std::vec![
+                #(::askql_parser::IntoValue::into_value(#bindings)),*
+            ])),
+        ),
+        Fields::Unit => (quote!(), quote!(::askql_parser::Value::Null)),
+    })
+}
+
+// Builds `path` from `value`, described as `what` in errors.
+fn fields_from_value(path: TokenStream, fields: &Fields, what: &str) -> syn::Result<TokenStream> {
+    Ok(match fields {
+        Fields::Named(named) => {
+            let names: Vec<&Ident> = named.named.iter().flat_map(|f| &f.ident).collect();
+            let keys = field_keys(fields)?;
+            let expected = format!("an object for {}", what);
+            quote! {
+                match value {
+                    ::askql_parser::Value::Object(mut object) => ::std::result::Result::Ok(#path {
+                        #(#names: ::askql_parser::take_field(&mut object, #keys)?,)*
+                    }),
+                    other => ::std::result::Result::Err(
+                        ::askql_parser::FromValueError::expected(#expected, &other)
+                    ),
+                }
+            }
+        }
+        Fields::Unnamed(unnamed) if unnamed.unnamed.len() == 1 => quote! {
+            ::std::result::Result::Ok(#path(::askql_parser::FromValue::from_value(value)?))
+        },
+        Fields::Unnamed(unnamed) => {
+            let len = unnamed.unnamed.len();
+            let indices = (0..len).map(Literal::usize_unsuffixed);
+            let expected = format!("a list of {} for {}", len, what);
+            quote! {
+                match value {
+                    ::askql_parser::Value::List(list) if list.len() == #len => {
+                        let mut list = ::std::iter::IntoIterator::into_iter(list);
+                        ::std::result::Result::Ok(#path(#(
+                            ::askql_parser::from_element(list.next().unwrap(), #indices)?
+                        ),*))
+                    }
+                    other => ::std::result::Result::Err(
+                        ::askql_parser::FromValueError::expected(#expected, &other)
+                    ),
+                }
+            }
+        }
+        Fields::Unit => {
+            let expected = format!("null for {}", what);
+            quote! {
+                match value {
+                    ::askql_parser::Value::Null => ::std::result::Result::Ok(#path),
+                    other => ::std::result::Result::Err(
+                        ::askql_parser::FromValueError::expected(#expected, &other)
+                    ),
+                }
+            }
+        }
+    })
+}
@@ -0,0 +1,370 @@
+//! The `askcode!` macro, which parses AskCode at compile time.
+//!
+//! ```
+//! use askql_macros::askcode;
+//!
+//! let name = "World";
+//! let code = askcode!("call(get('concat'), 'Hello ', $name, $mark)", mark = "!");
+//! ```
+//!
+//! The macro expands to an `askql_parser::AskCodeOrValue` expression, so the
+//! calling crate needs `askql-parser` as a dependency. Syntax errors are
+//! reported as compile errors on the string literal.
+//!
+//! `$name` interpolates a Rust expression where AskCode expects a value: the
+//! argument `name = expr` when there is one, otherwise the variable `name` in
+//! scope. The expression is converted with `Into<Value>`. Interpolations
+//! cannot stand for function names or object keys.
+//!
+//! `#[derive(IntoValue, FromValue)]` implements `askql_parser`'s conversion
+//! traits for structs and enums, in the shapes described in
+//! `askql_parser::convert`. Fields and variants are written under their
+//! names unless renamed with `#[askql(rename = "...")]`:
+//!
+//! ```
+//! use askql_macros::{FromValue, IntoValue};
+//! use askql_parser::{FromValue, IntoValue, Value};
+//!
+//! #[derive(Debug, PartialEq, IntoValue, FromValue)]
+//! struct Friend {
+//!     id: i64,
+//!     #[askql(rename = "nickName")]
+//!     nick_name: Option<String>,
+//! }
+//!
+//! let value = Friend { id: 1, nick_name: None }.into_value();
+//! assert_eq!(Friend::from_value(value), Ok(Friend { id: 1, nick_name: None }));
+//! ```
+
+extern crate proc_macro;
+
+mod derive;
+
+use askql_parser::{
+    format_date_time, format_duration, parse_with_spans, SpannedAskCodeOrValue, Value,
+};
+use proc_macro2::{Literal, TokenStream};
+use quote::quote;
+use std::collections::BTreeMap;
+use syn::parse::{Parse, ParseStream};
+use syn::{Expr, Ident, LitStr, Token};
+
+#[proc_macro]
+pub fn askcode(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    match syn::parse(input).and_then(expand) {
+        Ok(tokens) => tokens.into(),
+        Err(error) => error.to_compile_error().into(),
+    }
+}
+
+#[proc_macro_derive(IntoValue, attributes(askql))]
+pub fn derive_into_value(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    match syn::parse(input).and_then(derive::into_value) {
+        Ok(tokens) => tokens.into(),
+        Err(error) => error.to_compile_error().into(),
+    }
+}
+
+#[proc_macro_derive(FromValue, attributes(askql))]
+pub fn derive_from_value(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    match syn::parse(input).and_then(derive::from_value) {
+        Ok(tokens) => tokens.into(),
+        Err(error) => error.to_compile_error().into(),
+    }
+}
+
+struct Input {
+    code: LitStr,
+    args: Vec<(Ident, Expr)>,
+}
+
+impl Parse for Input {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let code = input.parse()?;
+        let mut args = Vec::new();
+        while !input.is_empty() {
+            input.parse::<Token![,]>()?;
+            if input.is_empty() {
+                break;
+            }
+            let name: Ident = input.parse()?;
+            input.parse::<Token![=]>()?;
+            args.push((name, input.parse()?));
+        }
+        Ok(Input { code, args })
+    }
+}
+
+// An interpolated `$name`, keyed by its byte offset in the source; `used`
+// records whether it stood for a value in the parsed tree.
+struct Interpolation {
+    name: String,
+    used: bool,
+}
+
+fn expand(input: Input) -> syn::Result<TokenStream> {
+    let source = input.code.value();
+    let span = input.code.span();
+    let error = |message: String| syn::Error::new(span, message);
+    let (code, mut interpolations) = scan_interpolations(&source);
+    let tree = parse_with_spans(code, false).map_err(|parse_error| {
+        let rendered = parse_error.render(&source);
+        error(rendered.trim_start_matches("error: ").to_string())
+    })?;
+
+    let mut args: BTreeMap<String, (Expr, bool)> = BTreeMap::new();
+    for (name, expr) in input.args {
+        let key = name.to_string();
+        if args.insert(key, (expr, false)).is_some() {
+            return Err(syn::Error::new(
+                name.span(),
+                format!("duplicate argument `{}`", name),
+            ));
+        }
+    }
+
+    let mut expander = Expander {
+        interpolations: &mut interpolations,
+        args: &mut args,
+        span,
+    };
+    let tokens = expander.code(&tree)?;
+
+    for (offset, interpolation) in &interpolations {
+        if !interpolation.used {
+            return Err(error(format!(
+                "`${}` at byte {} must stand for a value",
+                interpolation.name, offset
+            )));
+        }
+    }
+    for (name, (_, used)) in &args {
+        if !used {
+            return Err(error(format!("argument `{}` is never interpolated", name)));
+        }
+    }
+    Ok(tokens)
+}
+
+// Replaces every `$name` outside strings and comments with `_name`, which
+// parses as an identifier starting at the same offset, so the parsed tree and
+// any error positions line up with the original source.
+fn scan_interpolations(source: &str) -> (String, BTreeMap<usize, Interpolation>) {
+    let bytes = source.as_bytes();
+    let mut code = bytes.to_vec();
+    let mut interpolations = BTreeMap::new();
+    let mut index = 0;
+    while index < bytes.len() {
+        match bytes[index] {
+            quote @ b'\'' | quote @ b'"' => {
+                index += 1;
+                while index < bytes.len() && bytes[index] != quote {
+                    index += if bytes[index] == b'\\' { 2 } else { 1 };
+                }
+            }
+            b'/' if bytes.get(index + 1) == Some(&b'/') => {
+                while index < bytes.len() && bytes[index] != b'\n' {
+                    index += 1;
+                }
+            }
+            b'/' if bytes.get(index + 1) == Some(&b'*') => {
+                index += 2;
+                while index < bytes.len() && !bytes[index..].starts_with(b"*/") {
+                    index += 1;
+                }
+                index += 1;
+            }
+            b'$' if bytes
+                .get(index + 1)
+                .is_some_and(|byte| *byte == b'_' || byte.is_ascii_alphabetic()) =>
+            {
+                let start = index;
+                index += 1;
+                while bytes
+                    .get(index)
+                    .is_some_and(|byte| *byte == b'_' || byte.is_ascii_alphanumeric())
+                {
+                    index += 1;
+                }
+                code[start] = b'_';
+                interpolations.insert(
+                    start,
+                    Interpolation {
+                        name: source[start + 1..index].to_string(),
+                        used: false,
+                    },
+                );
+                continue;
+            }
+            _ => {}
+        }
+        index += 1;
+    }
+    // Only ASCII bytes were replaced, so the code is still valid UTF-8.
+    (String::from_utf8(code).unwrap(), interpolations)
+}
+
+struct Expander<'a> {
+    interpolations: &'a mut BTreeMap<usize, Interpolation>,
+    args: &'a mut BTreeMap<String, (Expr, bool)>,
+    span: proc_macro2::Span,
+}
+
+impl Expander<'_> {
+    fn code(&mut self, code: &SpannedAskCodeOrValue) -> syn::Result<TokenStream> {
+        match code {
+            SpannedAskCodeOrValue::Value(value, _) => {
+                let value = value_tokens(value);
+                Ok(quote!(::askql_parser::AskCodeOrValue::Value(#value)))
+            }
+            SpannedAskCodeOrValue::AskCode(askcode) => {
+                if askcode.params.is_none() {
+                    if let Some(interpolation) =
+                        self.interpolations.get_mut(&askcode.span.start.offset)
+                    {
+                        interpolation.used = true;
+                        let name = interpolation.name.clone();
+                        let expr = self.interpolated(&name);
+                        return Ok(quote!(::askql_parser::AskCodeOrValue::Value(
+                            ::std::convert::Into::<::askql_parser::Value>::into(#expr)
+                        )));
+                    }
+                }
+                let name = &askcode.name;
+                let params = match &askcode.params {
+                    None => quote!(::std::option::Option::None),
+                    Some(params) => {
+                        let params = params
+                            .iter()
+                            .map(|param| self.code(param))
+                            .collect::<syn::Result<Vec<_>>>()?;
+                        quote!(::std::option::Option::Some(::std::vec![#(#params),*]))
+                    }
+                };
+                Ok(quote!(::askql_parser::AskCodeOrValue::AskCode(
+                    ::askql_parser::AskCode::new(::std::string::String::from(#name), #params)
+                )))
+            }
+        }
+    }
+
+    // The expression `$name` stands for: its argument or a variable in scope.
+    fn interpolated(&mut self, name: &str) -> TokenStream {
+        match self.args.get_mut(name) {
+            Some((expr, used)) => {
+                *used = true;
+                quote!((#expr))
+            }
+            None => {
+                let ident = Ident::new(name, self.span);
+                quote!(#ident)
+            }
+        }
+    }
+}
+
+fn value_tokens(value: &Value) -> TokenStream {
+    match value {
+        Value::Null => quote!(::askql_parser::Value::Null),
+        Value::Boolean(boolean) => quote!(::askql_parser::Value::Boolean(#boolean)),
+        Value::Int(int) => quote!(::askql_parser::Value::Int(#int)),
+        Value::Float(float) => {
+            let float = if float.is_nan() {
+                quote!(::std::f64::NAN)
+            } else if float.is_infinite() && *float > 0.0 {
+                quote!(::std::f64::INFINITY)
+            } else if float.is_infinite() {
+                quote!(::std::f64::NEG_INFINITY)
+            } else {
+                let literal = Literal::f64_suffixed(*float);
+                quote!(#literal)
+            };
+            quote!(::askql_parser::Value::Float(#float))
+        }
+        Value::BigInt(int) => {
+            let int = int.to_string();
+            quote!(::askql_parser::Value::BigInt(
+                #int.parse::<::askql_parser::BigInt>().unwrap()
+            ))
+        }
+        Value::Decimal(decimal) => {
+            let decimal = decimal.to_string();
+            quote!(::askql_parser::Value::Decimal(
+                #decimal.parse::<::askql_parser::BigDecimal>().unwrap()
+            ))
+        }
+        Value::DateTime(date_time) => {
+            let date_time = format_date_time(date_time);
+            quote!(::askql_parser::Value::DateTime(
+                ::askql_parser::parse_date_time(#date_time).unwrap()
+            ))
+        }
+        Value::Duration(duration) => {
+            let duration = format_duration(duration);
+            quote!(::askql_parser::Value::Duration(
+                ::askql_parser::parse_duration(#duration).unwrap()
+            ))
+        }
+        Value::Number(number) => {
+            let number = &number.0;
+            quote!(::askql_parser::Value::Number(::askql_parser::Number(
+                ::std::string::String::from(#number)
+            )))
+        }
+        Value::String(string) => {
+            quote!(::askql_parser::Value::String(::std::string::String::from(#string)))
+        }
+        Value::Bytes(bytes) => {
+            quote!(::askql_parser::Value::Bytes(::std::vec![#(#bytes),*]))
+        }
+        Value::List(list) => {
+            let items = list.iter().map(value_tokens);
+            quote!(::askql_parser::Value::List(::std::vec![#(#items),*]))
+        }
+        Value::Object(object) => {
+            let keys = object.keys();
+            let values = object.values().map(value_tokens);
+            quote!(::askql_parser::Value::Object(
+                ::std::iter::Iterator::collect(::std::iter::IntoIterator::into_iter(::std::vec![
+                    #((::std::string::String::from(#keys), #values)),*
+                ]))
+            ))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn expand_error(input: TokenStream) -> String {
+        expand(syn::parse2(input).unwrap()).unwrap_err().to_string()
+    }
+
+    #[test]
+    fn test_syntax_errors() {
+        assert_eq!(
+            expand_error(quote!("ask(call(1;")),
+            "expected `,` or `)`, found `;`\n --> 1:11\n  |\n1 | ask(call(1;\n  |           ^"
+        );
+        assert_eq!(
+            expand_error(quote!("f($g(1))")),
+            "`$g` at byte 2 must stand for a value"
+        );
+        assert_eq!(
+            expand_error(quote!("f($x)", x = 1, y = 2)),
+            "argument `y` is never interpolated"
+        );
+    }
+
+    #[test]
+    fn test_scan_interpolations() {
+        let (code, interpolations) = scan_interpolations("f($a, '$b', /* $c */ $_d1)");
+        assert_eq!(code, "f(_a, '$b', /* $c */ __d1)");
+        let names: Vec<(usize, &str)> = interpolations
+            .iter()
+            .map(|(offset, interpolation)| (*offset, interpolation.name.as_str()))
+            .collect();
+        assert_eq!(names, vec![(2, "a"), (21, "_d1")]);
+    }
+}
@@ -0,0 +1,35 @@
+use askql_macros::askcode;
+use askql_parser::{parse, AskCodeOrValue, Value};
+
+fn parsed(code: &str) -> AskCodeOrValue {
+    parse(code.to_string(), false).unwrap()
+}
+
+#[test]
+fn test_askcode() {
+    assert_eq!(
+        askcode!("ask(call(get('+'), 1, -2.5), [x, 'a'], {key: f()})"),
+        parsed("ask(call(get('+'), 1, -2.5), [x, 'a'], {key: f()})")
+    );
+    assert_eq!(askcode!("'$name' // $comment"), parsed("'$name'"));
+}
+
+#[test]
+fn test_interpolation() {
+    let name = "World";
+    let count = 3;
+    let code = askcode!(
+        "call(get('concat'), $name, $mark, [$count])",
+        mark = '!'.to_string()
+    );
+    let call = match parsed("call(get('concat'), 'World', '!', [])") {
+        AskCodeOrValue::AskCode(mut call) => {
+            if let Some(AskCodeOrValue::AskCode(list)) = call.params.as_mut().unwrap().last_mut() {
+                list.params = Some(vec![AskCodeOrValue::Value(Value::Int(count))]);
+            }
+            AskCodeOrValue::AskCode(call)
+        }
+        value => value,
+    };
+    assert_eq!(code, call);
+}
@@ -0,0 +1,135 @@
+use askql_macros::{FromValue, IntoValue};
+use askql_parser::{FromValue, IntoValue, Object, Value};
+
+#[derive(Debug, Clone, PartialEq, IntoValue, FromValue)]
+struct Friend {
+    id: i64,
+    name: String,
+    tags: Vec<String>,
+    #[askql(rename = "bestFriend")]
+    best_friend: Option<Box<Friend>>,
+}
+
+#[derive(Debug, Clone, PartialEq, IntoValue, FromValue)]
+struct Point(f64, f64);
+
+#[derive(Debug, Clone, PartialEq, IntoValue, FromValue)]
+struct Id(u32);
+
+#[derive(Debug, Clone, PartialEq, IntoValue, FromValue)]
+enum Shape {
+    #[askql(rename = "none")]
+    Empty,
+    Circle {
+        center: Point,
+        radius: f64,
+    },
+    Polygon(Vec<Point>),
+}
+
+#[derive(Debug, Clone, PartialEq, IntoValue, FromValue)]
+struct Wrapper<T> {
+    inner: T,
+}
+
+fn object(entries: Vec<(&str, Value)>) -> Value {
+    Value::Object(
+        entries
+            .into_iter()
+            .map(|(key, value)| (key.to_string(), value))
+            .collect::<Object>(),
+    )
+}
+
+#[test]
+fn test_struct() {
+    let friend = Friend {
+        id: 1,
+        name: "Ana".to_string(),
+        tags: vec!["x".to_string()],
+        best_friend: None,
+    };
+    let value = friend.clone().into_value();
+    assert_eq!(
+        value,
+        object(vec![
+            ("id", Value::Int(1)),
+            ("name", Value::String("Ana".to_string())),
+            ("tags", Value::List(vec![Value::String("x".to_string())])),
+            ("bestFriend", Value::Null),
+        ])
+    );
+    assert_eq!(Friend::from_value(value), Ok(friend));
+    assert_eq!(
+        Point(1.0, 2.5).into_value(),
+        Value::List(vec![Value::Float(1.0), Value::Float(2.5)])
+    );
+    assert_eq!(Id::from_value(Value::Int(7)), Ok(Id(7)));
+    assert_eq!(
+        Wrapper::<bool>::from_value(object(vec![("inner", Value::Boolean(true))])),
+        Ok(Wrapper { inner: true })
+    );
+}
+
+#[test]
+fn test_enum() {
+    assert_eq!(Shape::Empty.into_value(), Value::String("none".to_string()));
+    assert_eq!(
+        Shape::from_value(Value::String("none".to_string())),
+        Ok(Shape::Empty)
+    );
+    let circle = Shape::Circle {
+        center: Point(0.0, 0.0),
+        radius: 2.0,
+    };
+    let value = circle.clone().into_value();
+    assert_eq!(
+        value,
+        object(vec![(
+            "Circle",
+            object(vec![
+                (
+                    "center",
+                    Value::List(vec![Value::Float(0.0), Value::Float(0.0)])
+                ),
+                ("radius", Value::Float(2.0)),
+            ])
+        )])
+    );
+    assert_eq!(Shape::from_value(value), Ok(circle));
+    let polygon = Shape::Polygon(vec![Point(1.0, 1.0)]);
+    assert_eq!(Shape::from_value(polygon.clone().into_value()), Ok(polygon));
+}
+
+#[test]
+fn test_errors() {
+    let error = |value: Value| Friend::from_value(value).unwrap_err().to_string();
+    assert_eq!(
+        error(object(vec![("id", Value::Int(1))])),
+        "missing field `name`"
+    );
+    assert_eq!(
+        error(object(vec![
+            ("id", Value::Int(1)),
+            ("name", Value::String("Ana".to_string())),
+            ("tags", Value::List(vec![Value::Int(2)])),
+        ])),
+        "expected string, found int at `tags[0]`"
+    );
+    assert_eq!(
+        error(Value::Null),
+        "expected an object for Friend, found null"
+    );
+    assert_eq!(
+        Shape::from_value(Value::String("Square".to_string()))
+            .unwrap_err()
+            .to_string(),
+        "unknown variant `Square` of Shape"
+    );
+    assert_eq!(
+        Shape::from_value(object(vec![("Polygon", Value::List(vec![Value::Null]))]))
+            .unwrap_err()
+            .to_string(),
+        "expected a list of 2 for Point, found null at `Polygon[0]`"
+    );
+}
@@ -0,0 +1,31 @@
+use askql_parser::parse;
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+
+// A generated program of `items` calls, like the ones our query builders
+// produce, with identifiers, numbers, strings, lists and objects.
+fn program(items: usize) -> String {
+    let calls: Vec<String> = (0..items)
+        .map(|item| {
+            format!(
+                "call(get('concat'), firstName_{}, 'value \\'{}\\'', [{}, 2.5e3], {{ key: -{} }})",
+                item, item, item, item
+            )
+        })
+        .collect();
+    format!("ask(\n  {}\n)", calls.join(",\n  "))
+}
+
+fn bench_parse(c: &mut Criterion) {
+    let mut group = c.benchmark_group("parse");
+    for items in [100, 1_000, 10_000].iter() {
+        let code = program(*items);
+        group.throughput(Throughput::Bytes(code.len() as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(items), &code, |b, code| {
+            b.iter(|| parse(black_box(code.clone()), false).unwrap())
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_parse);
+criterion_main!(benches);
@@ -1,11 +1,20 @@
-use crate::reduce::Reducer;
+use crate::print::{print, PrintStyle};
+use crate::reduce::{ParseError, Reducer, ERROR_NODE};
+use crate::span::{Span, SpanReducer};
 use crate::value::Value;
 use std::collections::BTreeMap;
 use std::fmt;
 
-#[derive(Debug, Clone, Eq, PartialEq, Serialize)]
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
 pub enum AskCodeOrValue {
-    Value(Value),
+    /// Written in the tagged form of `json`.
+    Value(
+        #[serde(
+            serialize_with = "crate::json::serialize_value",
+            deserialize_with = "crate::json::deserialize_value"
+        )]
+        Value,
+    ),
     AskCode(AskCode),
 }
 
@@ -19,7 +28,7 @@ impl AskCodeOrValue {
     }
 }
 
-#[derive(Debug, Clone, Eq, PartialEq, Serialize)]
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
 pub struct AskCode {
     pub name: String,
     pub params: Option<Vec<AskCodeOrValue>>,
@@ -31,17 +40,17 @@ impl AskCode {
     }
 }
 pub trait AskCodeTrait: std::fmt::Debug + Clone + Sized {
-    fn name(&self) -> String;
-    fn params(&self) -> Option<Vec<AskCodeOrValue>>;
+    fn name(&self) -> &str;
+    fn params(&self) -> Option<&[AskCodeOrValue]>;
 }
 
 impl AskCodeTrait for AskCode {
-    fn name(&self) -> String {
-        self.name.clone()
+    fn name(&self) -> &str {
+        &self.name
     }
 
-    fn params(&self) -> Option<Vec<AskCodeOrValue>> {
-        self.params.clone()
+    fn params(&self) -> Option<&[AskCodeOrValue]> {
+        self.params.as_deref()
     }
 }
 
@@ -62,6 +71,88 @@ impl Reducer<AskCodeOrValue> for AskCodeReducer {
     }
 }
 
+#[derive(Debug, Clone, Eq, PartialEq, Serialize)]
+pub enum SpannedAskCodeOrValue {
+    Value(Value, Span),
+    AskCode(SpannedAskCode),
+}
+
+impl SpannedAskCodeOrValue {
+    pub fn span(&self) -> Span {
+        match self {
+            SpannedAskCodeOrValue::Value(_, span) => *span,
+            SpannedAskCodeOrValue::AskCode(askcode) => askcode.span,
+        }
+    }
+
+    pub fn into_ask_code_or_value(self) -> AskCodeOrValue {
+        match self {
+            SpannedAskCodeOrValue::Value(value, _) => AskCodeOrValue::Value(value),
+            SpannedAskCodeOrValue::AskCode(askcode) => {
+                AskCodeOrValue::AskCode(askcode.into_ask_code())
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Eq, PartialEq, Serialize)]
+pub struct SpannedAskCode {
+    pub name: String,
+    pub params: Option<Vec<SpannedAskCodeOrValue>>,
+    pub span: Span,
+}
+
+impl SpannedAskCode {
+    pub fn into_ask_code(self) -> AskCode {
+        AskCode::new(
+            self.name,
+            self.params.map(|params| {
+                params
+                    .into_iter()
+                    .map(SpannedAskCodeOrValue::into_ask_code_or_value)
+                    .collect()
+            }),
+        )
+    }
+}
+
+pub struct SpannedAskCodeReducer {}
+
+impl SpanReducer<SpannedAskCodeOrValue> for SpannedAskCodeReducer {
+    fn node(
+        &self,
+        name: String,
+        children: Option<Vec<SpannedAskCodeOrValue>>,
+        span: Span,
+    ) -> SpannedAskCodeOrValue {
+        SpannedAskCodeOrValue::AskCode(SpannedAskCode {
+            name,
+            params: children,
+            span,
+        })
+    }
+    fn id(&self, name: String, span: Span) -> SpannedAskCodeOrValue {
+        SpannedAskCodeOrValue::AskCode(SpannedAskCode {
+            name,
+            params: None,
+            span,
+        })
+    }
+    fn value(&self, value: Value, span: Span) -> SpannedAskCodeOrValue {
+        SpannedAskCodeOrValue::Value(value, span)
+    }
+    fn error(&self, error: &ParseError, span: Span) -> SpannedAskCodeOrValue {
+        SpannedAskCodeOrValue::AskCode(SpannedAskCode {
+            name: ERROR_NODE.to_string(),
+            params: Some(vec![SpannedAskCodeOrValue::Value(
+                Value::String(error.to_string()),
+                span,
+            )]),
+            span,
+        })
+    }
+}
+
 pub fn is_ask_code<T: AskCodeTrait>(value: &AskCodeOrValue) -> bool {
     use AskCodeOrValue::*;
     match value {
@@ -79,29 +170,7 @@ pub fn is_value<T: AskCodeTrait>(value: &AskCodeOrValue) -> bool {
 }
 
 pub fn ask_code_to_source(value: &AskCodeOrValue) -> String {
-    match value {
-        AskCodeOrValue::Value(value) => match value {
-            Value::String(string) => string.clone(),
-            val => format!("{}", val),
-        },
-        AskCodeOrValue::AskCode(askcode) => {
-            let params = match askcode.params() {
-                Some(params) => params
-                    .iter()
-                    .map(ask_code_to_source)
-                    .collect::<Vec<String>>()
-                    .join(","),
-                None => "".to_string(),
-            };
-            format!(
-                "{} {{
-                {}
-            }}",
-                askcode.name(),
-                params
-            )
-        }
-    }
+    print(value, PrintStyle::Compact)
 }
 
 #[derive(Debug)]
@@ -123,3 +192,17 @@ pub fn value(value: &AskCodeOrValue) -> Result<Value, AskCordOrValueError> {
         AskCodeOrValue::AskCode(_) => Err(AskCordOrValueError::ExpectingValue),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse::parse;
+
+    #[test]
+    fn test_string_to_source() {
+        let string = Value::String("it's\na \\ \"test\"".to_string());
+        let source = ask_code_to_source(&AskCodeOrValue::Value(string.clone()));
+        assert_eq!(source, r#"'it\'s\na \\ "test"'"#);
+        assert_eq!(parse(source, false), Ok(AskCodeOrValue::Value(string)));
+    }
+}
@@ -0,0 +1,1172 @@
+use crate::diagnostic::Diagnostic;
+use crate::escape::unescape;
+use crate::number::scan_number;
+use crate::reduce::{ParseError, ParserOptions};
+use crate::span::{SourceMap, Span, SpanReducer};
+use crate::value::{Number, Value};
+use log::info;
+use std::collections::{HashMap, HashSet};
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Id(String),
+    Number(String),
+    String(String),
+    Punct(&'static str),
+    End,
+}
+
+const PUNCTUATION: [&str; 23] = [
+    "==", "!=", "<=", ">=", "&&", "||", "+", "-", "*", "/", "%", "<", ">", "!", "=", "(", ")", "{",
+    "}", "[", "]", ",", ":",
+];
+
+#[derive(Debug, Clone)]
+struct Expr {
+    kind: ExprKind,
+    span: Span,
+}
+
+#[derive(Debug, Clone)]
+enum ExprKind {
+    Id(String),
+    Value(Value),
+    Call(Box<Expr>, Vec<Expr>),
+    Operator(String, Span, Vec<Expr>),
+    // `and(a, b)` or `or(a, b)`, a node rather than a call so that the
+    // resource evaluates `b` only when it needs to.
+    Logic(String, Vec<Expr>),
+    List(Vec<Expr>),
+    // Entries are `List`s of a key and a value.
+    Object(Vec<Expr>),
+    Block(String, Vec<Statement>),
+    Query(Vec<Field>),
+    // `let(name, value)`, which binds `name` for the rest of its block.
+    Bind(String, Box<Expr>),
+}
+
+impl Expr {
+    fn new(kind: ExprKind, span: Span) -> Self {
+        Self { kind, span }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Statement {
+    kind: StatementKind,
+    span: Span,
+}
+
+#[derive(Debug, Clone)]
+enum StatementKind {
+    Const(String, Expr),
+    Let(String, Expr),
+    Assign(String, Expr),
+    Return(Expr),
+    Expr(Expr),
+}
+
+#[derive(Debug, Clone)]
+struct Field {
+    name: String,
+    name_span: Span,
+    value: Option<Expr>,
+    children: Vec<Field>,
+    span: Span,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Binding {
+    Const,
+    Let,
+}
+
+pub struct AskScriptParser {
+    code: String,
+    source_map: SourceMap,
+    tokens: Vec<(Token, Span)>,
+    index: usize,
+    steps: usize,
+    depth: usize,
+    nodes: usize,
+    options: ParserOptions,
+    logging: bool,
+}
+
+impl AskScriptParser {
+    pub fn new(code: String, stop_after_steps: Option<usize>, logging: bool) -> Self {
+        let options = ParserOptions {
+            max_steps: stop_after_steps,
+            ..ParserOptions::default()
+        };
+        Self::with_options(code, options, logging)
+    }
+
+    pub fn with_options(code: String, options: ParserOptions, logging: bool) -> Self {
+        Self {
+            source_map: SourceMap::new(&code),
+            code,
+            tokens: Vec::new(),
+            index: 0,
+            steps: 0,
+            depth: 0,
+            nodes: 0,
+            options,
+            logging,
+        }
+    }
+
+    pub fn reduce<R, T>(&mut self, reducer: R) -> Result<T, ParseError>
+    where
+        R: SpanReducer<T>,
+        T: std::fmt::Debug,
+    {
+        if let Some(max_length) = self.options.max_length {
+            if self.code.len() > max_length {
+                return Err(ParseError::ExceedMaxLength(max_length));
+            }
+        }
+        self.tokens = self.tokenize()?;
+        self.index = 0;
+        if self.tokens.len() == 1 {
+            return Err(ParseError::EmptyProgram);
+        }
+        let statements = self.statements()?;
+        if !self.is_at(&Token::End) {
+            return Err(self.expecting(&["end of input"]));
+        }
+        let span = self.span(0);
+        let mut statements = lower_statements(statements, &HashMap::new())?;
+        if let Some(max_nodes) = self.options.max_nodes {
+            // The limit is on the AskCode output, which is larger than what
+            // `count_nodes` saw: each identifier becomes `get('x')`.
+            let wrapper = if statements.len() == 1 { 0 } else { 1 };
+            if wrapper + statements.iter().map(statement_nodes).sum::<usize>() > max_nodes {
+                return Err(ParseError::ExceedMaxNodes(max_nodes));
+            }
+        }
+        if statements.len() == 1 {
+            Ok(lower(statements.remove(0), &reducer))
+        } else {
+            Ok(lower_expr(
+                Expr::new(ExprKind::Block("ask".to_string(), statements), span),
+                &reducer,
+            ))
+        }
+    }
+
+    fn step(&mut self, message: String) -> Result<(), ParseError> {
+        self.steps += 1;
+        match self.options.max_steps {
+            Some(after_steps) if self.steps > after_steps => {
+                Err(ParseError::ExceedMaxStpes(after_steps))
+            }
+            _ => {
+                if self.logging {
+                    info!("{}", message);
+                    println!("{}", message);
+                }
+                Ok(())
+            }
+        }
+    }
+
+    fn tokenize(&self) -> Result<Vec<(Token, Span)>, ParseError> {
+        let code = self.code.as_str();
+        let bytes = code.as_bytes();
+        let mut tokens = Vec::new();
+        let mut index = 0;
+        'tokens: while index < bytes.len() {
+            let rest = &code[index..];
+            let trimmed = rest.trim_start_matches(|char: char| char.is_whitespace() || char == ';');
+            if trimmed.len() < rest.len() {
+                index += rest.len() - trimmed.len();
+                continue;
+            }
+            if rest.starts_with("//") {
+                index += rest.find('\n').unwrap_or(rest.len());
+                continue;
+            }
+            if let Some(comment) = rest.strip_prefix("/*") {
+                match comment.find("*/") {
+                    Some(end) => index += end + 4,
+                    None => {
+                        return Err(ParseError::Syntax(Diagnostic::expecting(
+                            vec!["`*/`".to_string()],
+                            None,
+                            self.source_map.span(index, bytes.len()),
+                        )))
+                    }
+                }
+                continue;
+            }
+            let start = index;
+            let byte = bytes[index];
+            if byte == b'_' || byte.is_ascii_alphabetic() {
+                while index < bytes.len()
+                    && (bytes[index] == b'_' || bytes[index].is_ascii_alphanumeric())
+                {
+                    index += 1;
+                }
+                tokens.push((
+                    Token::Id(code[start..index].to_string()),
+                    self.source_map.span(start, index),
+                ));
+                continue;
+            }
+            if byte.is_ascii_digit() {
+                let (number, end) = scan_number(code, index).map_err(|error| {
+                    ParseError::Syntax(Diagnostic::new(
+                        error.message,
+                        self.source_map.span(start, error.end),
+                    ))
+                })?;
+                index = end;
+                tokens.push((Token::Number(number), self.source_map.span(start, index)));
+                continue;
+            }
+            if byte == b'\'' || byte == b'"' {
+                index += 1;
+                let mut string = String::new();
+                loop {
+                    let run_start = index;
+                    while index < bytes.len() && bytes[index] != byte && bytes[index] != b'\\' {
+                        index += 1;
+                    }
+                    string.push_str(&code[run_start..index]);
+                    if index == bytes.len() || bytes[index] == byte {
+                        break;
+                    }
+                    let (char, end) = unescape(code, index).map_err(|error| {
+                        ParseError::Syntax(Diagnostic::new(
+                            error.message,
+                            self.source_map.span(index, error.end),
+                        ))
+                    })?;
+                    string.push(char);
+                    index = end;
+                }
+                if index == bytes.len() {
+                    return Err(ParseError::Syntax(Diagnostic::expecting(
+                        vec![format!("`{}`", byte as char)],
+                        None,
+                        self.source_map.span(start, index),
+                    )));
+                }
+                index += 1;
+                tokens.push((Token::String(string), self.source_map.span(start, index)));
+                continue;
+            }
+            for punct in PUNCTUATION.iter() {
+                if rest.starts_with(punct) {
+                    index += punct.len();
+                    tokens.push((Token::Punct(punct), self.source_map.span(start, index)));
+                    continue 'tokens;
+                }
+            }
+            let char = rest.chars().next().unwrap();
+            return Err(ParseError::Syntax(Diagnostic::new(
+                format!("unexpected character `{}`", char),
+                self.source_map.span(index, index + char.len_utf8()),
+            )));
+        }
+        tokens.push((Token::End, self.source_map.span(bytes.len(), bytes.len())));
+        Ok(tokens)
+    }
+
+    fn peek(&self) -> &Token {
+        &self.tokens[self.index].0
+    }
+
+    fn peek_at(&self, offset: usize) -> &Token {
+        let index = (self.index + offset).min(self.tokens.len() - 1);
+        &self.tokens[index].0
+    }
+
+    fn peek_span(&self) -> Span {
+        self.tokens[self.index].1
+    }
+
+    // Span from the token at `start` up to the last consumed token.
+    fn span(&self, start: usize) -> Span {
+        let end = if self.index > start {
+            self.tokens[self.index - 1].1
+        } else {
+            self.tokens[start].1
+        };
+        self.tokens[start].1.to(end)
+    }
+
+    fn is_at(&self, token: &Token) -> bool {
+        self.peek() == token
+    }
+
+    fn is_at_punct(&self, punct: &str) -> bool {
+        matches!(self.peek(), Token::Punct(p) if *p == punct)
+    }
+
+    fn is_at_keyword(&self, keyword: &str) -> bool {
+        matches!(self.peek(), Token::Id(id) if id == keyword)
+    }
+
+    fn advance(&mut self) {
+        if self.index < self.tokens.len() - 1 {
+            self.index += 1;
+        }
+    }
+
+    fn expecting(&self, expected: &[&str]) -> ParseError {
+        let found = match self.peek() {
+            Token::Id(name) => Some(format!("`{}`", name)),
+            Token::Number(number) => Some(format!("`{}`", number)),
+            Token::String(_) => Some("string".to_string()),
+            Token::Punct(punct) => Some(format!("`{}`", punct)),
+            Token::End => None,
+        };
+        ParseError::Syntax(Diagnostic::expecting(
+            expected
+                .iter()
+                .map(|expected| expected.to_string())
+                .collect(),
+            found,
+            self.peek_span(),
+        ))
+    }
+
+    fn process(&mut self, punct: &'static str) -> Result<(), ParseError> {
+        if !self.is_at_punct(punct) {
+            return Err(self.expecting(&[&format!("`{}`", punct)]));
+        }
+        self.step(format!("process({})", punct))?;
+        self.advance();
+        Ok(())
+    }
+
+    fn id(&mut self) -> Result<String, ParseError> {
+        self.step("id".to_string())?;
+        match self.peek().clone() {
+            Token::Id(name) => {
+                self.advance();
+                Ok(name)
+            }
+            _ => Err(self.expecting(&["identifier"])),
+        }
+    }
+
+    // Counts `count` more nodes of the program.
+    fn count_nodes(&mut self, count: usize) -> Result<(), ParseError> {
+        self.nodes += count;
+        match self.options.max_nodes {
+            Some(max_nodes) if self.nodes > max_nodes => Err(ParseError::ExceedMaxNodes(max_nodes)),
+            _ => Ok(()),
+        }
+    }
+
+    // Runs `parse` one nesting level deeper, failing before it recurses past
+    // the maximum depth.
+    fn nested<T>(
+        &mut self,
+        parse: impl FnOnce(&mut Self) -> Result<T, ParseError>,
+    ) -> Result<T, ParseError> {
+        if let Some(max_depth) = self.options.max_depth {
+            if self.depth >= max_depth {
+                return Err(ParseError::ExceedMaxDepth(max_depth));
+            }
+        }
+        self.depth += 1;
+        let result = parse(self);
+        self.depth -= 1;
+        result
+    }
+
+    fn statements(&mut self) -> Result<Vec<Statement>, ParseError> {
+        let mut statements = Vec::new();
+        while !self.is_at(&Token::End) && !self.is_at_punct("}") {
+            statements.push(self.statement()?);
+        }
+        Ok(statements)
+    }
+
+    fn block(&mut self) -> Result<Vec<Statement>, ParseError> {
+        self.process("{")?;
+        let statements = self.statements()?;
+        self.process("}")?;
+        Ok(statements)
+    }
+
+    fn statement(&mut self) -> Result<Statement, ParseError> {
+        self.step("statement".to_string())?;
+        let start = self.index;
+        let kind = if self.is_at_keyword("const") || self.is_at_keyword("let") {
+            let is_const = self.is_at_keyword("const");
+            self.advance();
+            let name = self.id()?;
+            self.process("=")?;
+            let value = self.expression()?;
+            if is_const {
+                StatementKind::Const(name, value)
+            } else {
+                StatementKind::Let(name, value)
+            }
+        } else if self.is_at_keyword("return") {
+            self.advance();
+            StatementKind::Return(self.expression()?)
+        } else if let (Token::Id(name), Token::Punct("=")) = (self.peek().clone(), self.peek_at(1))
+        {
+            self.advance();
+            self.advance();
+            StatementKind::Assign(name, self.expression()?)
+        } else {
+            StatementKind::Expr(self.expression()?)
+        };
+        Ok(Statement {
+            kind,
+            span: self.span(start),
+        })
+    }
+
+    fn expression(&mut self) -> Result<Expr, ParseError> {
+        self.step("expression".to_string())?;
+        self.nested(|parser| parser.binary(0))
+    }
+
+    fn binary(&mut self, level: usize) -> Result<Expr, ParseError> {
+        const LEVELS: [&[&str]; 6] = [
+            &["||"],
+            &["&&"],
+            &["==", "!="],
+            &["<", ">", "<=", ">="],
+            &["+", "-"],
+            &["*", "/", "%"],
+        ];
+        if level == LEVELS.len() {
+            return self.unary();
+        }
+        let start = self.index;
+        let mut left = self.binary(level + 1)?;
+        while let Token::Punct(punct) = self.peek().clone() {
+            if !LEVELS[level].contains(&punct) {
+                break;
+            }
+            let operator_span = self.peek_span();
+            self.advance();
+            self.count_nodes(1)?;
+            let right = self.binary(level + 1)?;
+            let kind = match punct {
+                "&&" => ExprKind::Logic("and".to_string(), vec![left, right]),
+                "||" => ExprKind::Logic("or".to_string(), vec![left, right]),
+                _ => ExprKind::Operator(operator_resource(punct), operator_span, vec![left, right]),
+            };
+            left = Expr::new(kind, self.span(start));
+        }
+        Ok(left)
+    }
+
+    fn unary(&mut self) -> Result<Expr, ParseError> {
+        let start = self.index;
+        if self.is_at_punct("-") {
+            let operator_span = self.peek_span();
+            self.advance();
+            let expr = self.nested(Self::unary)?;
+            let span = self.span(start);
+            return Ok(match expr.kind {
+                ExprKind::Value(Value::Number(Number(number))) if !number.starts_with('-') => {
+                    Expr::new(
+                        ExprKind::Value(Value::Number(Number(format!("-{}", number)))),
+                        span,
+                    )
+                }
+                kind => Expr::new(
+                    ExprKind::Operator(
+                        "-".to_string(),
+                        operator_span,
+                        vec![
+                            Expr::new(
+                                ExprKind::Value(Value::Number(Number("0".to_string()))),
+                                operator_span,
+                            ),
+                            Expr::new(kind, expr.span),
+                        ],
+                    ),
+                    span,
+                ),
+            });
+        }
+        if self.is_at_punct("!") {
+            let operator_span = self.peek_span();
+            self.advance();
+            self.count_nodes(1)?;
+            let expr = self.nested(Self::unary)?;
+            return Ok(Expr::new(
+                ExprKind::Operator("not".to_string(), operator_span, vec![expr]),
+                self.span(start),
+            ));
+        }
+        self.postfix()
+    }
+
+    fn postfix(&mut self) -> Result<Expr, ParseError> {
+        let start = self.index;
+        let mut expr = self.primary()?;
+        loop {
+            if self.is_at_punct("(") || self.is_at_punct(":") {
+                self.count_nodes(1)?;
+            }
+            if self.is_at_punct("(") {
+                let args = self.expression_list("(", ")")?;
+                expr = Expr::new(ExprKind::Call(Box::new(expr), args), self.span(start));
+            } else if self.is_at_punct(":") {
+                self.advance();
+                let method_start = self.index;
+                let method = Expr::new(ExprKind::Id(self.id()?), self.span(method_start));
+                let mut args = vec![expr];
+                if self.is_at_punct("(") {
+                    args.extend(self.expression_list("(", ")")?);
+                }
+                expr = Expr::new(ExprKind::Call(Box::new(method), args), self.span(start));
+            } else {
+                return Ok(expr);
+            }
+        }
+    }
+
+    fn expression_list(
+        &mut self,
+        open: &'static str,
+        close: &'static str,
+    ) -> Result<Vec<Expr>, ParseError> {
+        self.process(open)?;
+        let mut values = Vec::new();
+        while !self.is_at_punct(close) {
+            values.push(self.expression()?);
+            if !self.is_at_punct(close) {
+                if !self.is_at_punct(",") {
+                    return Err(self.expecting(&["`,`", &format!("`{}`", close)]));
+                }
+                self.process(",")?;
+            }
+        }
+        self.process(close)?;
+        Ok(values)
+    }
+
+    fn entries(&mut self) -> Result<Vec<Expr>, ParseError> {
+        self.process("{")?;
+        let mut entries = Vec::new();
+        while !self.is_at_punct("}") {
+            self.step("entry".to_string())?;
+            // The key and the `list` node holding the pair.
+            self.count_nodes(2)?;
+            let start = self.index;
+            let key = match self.peek().clone() {
+                Token::Id(key) | Token::String(key) => {
+                    self.advance();
+                    Expr::new(ExprKind::Value(Value::String(key)), self.span(start))
+                }
+                _ => return Err(self.expecting(&["key"])),
+            };
+            self.process(":")?;
+            let value = self.expression()?;
+            entries.push(Expr::new(
+                ExprKind::List(vec![key, value]),
+                self.span(start),
+            ));
+            if !self.is_at_punct("}") {
+                if !self.is_at_punct(",") {
+                    return Err(self.expecting(&["`,`", "`}`"]));
+                }
+                self.process(",")?;
+            }
+        }
+        self.process("}")?;
+        Ok(entries)
+    }
+
+    fn primary(&mut self) -> Result<Expr, ParseError> {
+        self.count_nodes(1)?;
+        let start = self.index;
+        let kind = match self.peek().clone() {
+            Token::Number(number) => {
+                self.step("number".to_string())?;
+                self.advance();
+                ExprKind::Value(Value::Number(Number(number)))
+            }
+            Token::String(string) => {
+                self.step("string".to_string())?;
+                self.advance();
+                ExprKind::Value(Value::String(string))
+            }
+            Token::Punct("(") => {
+                self.advance();
+                let expr = self.expression()?;
+                self.process(")")?;
+                return Ok(expr);
+            }
+            Token::Punct("[") => ExprKind::List(self.expression_list("[", "]")?),
+            Token::Punct("{") => ExprKind::Object(self.entries()?),
+            Token::Id(id) => match id.as_str() {
+                "ask" => {
+                    self.advance();
+                    ExprKind::Block("ask".to_string(), self.block()?)
+                }
+                "fun" => {
+                    self.advance();
+                    if self.is_at_punct("(") {
+                        self.advance();
+                        if !self.is_at_punct(")") {
+                            return Err(self.expecting(&["`)`"]));
+                        }
+                        self.advance();
+                    }
+                    ExprKind::Block("f".to_string(), self.block()?)
+                }
+                "query" => {
+                    self.advance();
+                    ExprKind::Query(self.fields()?)
+                }
+                "true" | "false" => {
+                    self.advance();
+                    ExprKind::Value(Value::Boolean(id == "true"))
+                }
+                "null" => {
+                    self.advance();
+                    ExprKind::Value(Value::Null)
+                }
+                "const" | "let" | "return" => return Err(self.expecting(&["expression"])),
+                _ => {
+                    self.advance();
+                    ExprKind::Id(id)
+                }
+            },
+            _ => return Err(self.expecting(&["expression"])),
+        };
+        Ok(Expr::new(kind, self.span(start)))
+    }
+
+    fn fields(&mut self) -> Result<Vec<Field>, ParseError> {
+        self.process("{")?;
+        let mut fields = Vec::new();
+        while !self.is_at_punct("}") {
+            self.step("field".to_string())?;
+            self.count_nodes(1)?;
+            let start = self.index;
+            let name_span = self.peek_span();
+            let name = self.id()?;
+            let value = if self.is_at_punct(":") {
+                self.advance();
+                Some(self.expression()?)
+            } else {
+                None
+            };
+            let children = if self.is_at_punct("{") {
+                self.nested(Self::fields)?
+            } else {
+                Vec::new()
+            };
+            fields.push(Field {
+                name,
+                name_span,
+                value,
+                children,
+                span: self.span(start),
+            });
+        }
+        self.process("}")?;
+        Ok(fields)
+    }
+}
+
+fn operator_resource(operator: &str) -> String {
+    match operator {
+        "==" => "equals".to_string(),
+        operator => operator.to_string(),
+    }
+}
+
+// Lowers the blocks inside `expr`, which see the bindings in `scope`.
+fn resolve(expr: Expr, scope: &HashMap<String, Binding>) -> Result<Expr, ParseError> {
+    let resolve_all = |exprs: Vec<Expr>| -> Result<Vec<Expr>, ParseError> {
+        exprs.into_iter().map(|expr| resolve(expr, scope)).collect()
+    };
+    let kind = match expr.kind {
+        ExprKind::Id(name) => ExprKind::Id(name),
+        ExprKind::Value(value) => ExprKind::Value(value),
+        ExprKind::Call(callee, args) => {
+            ExprKind::Call(Box::new(resolve(*callee, scope)?), resolve_all(args)?)
+        }
+        ExprKind::Operator(name, span, args) => ExprKind::Operator(name, span, resolve_all(args)?),
+        ExprKind::Logic(name, args) => ExprKind::Logic(name, resolve_all(args)?),
+        ExprKind::List(items) => ExprKind::List(resolve_all(items)?),
+        ExprKind::Object(entries) => ExprKind::Object(resolve_all(entries)?),
+        ExprKind::Block(name, statements) => {
+            ExprKind::Block(name, lower_statements(statements, scope)?)
+        }
+        ExprKind::Query(fields) => ExprKind::Query(resolve_fields(fields, scope)?),
+        ExprKind::Bind(name, value) => ExprKind::Bind(name, Box::new(resolve(*value, scope)?)),
+    };
+    Ok(Expr::new(kind, expr.span))
+}
+
+fn resolve_fields(
+    fields: Vec<Field>,
+    scope: &HashMap<String, Binding>,
+) -> Result<Vec<Field>, ParseError> {
+    fields
+        .into_iter()
+        .map(|field| {
+            Ok(Field {
+                value: match field.value {
+                    Some(value) => Some(resolve(value, scope)?),
+                    None => None,
+                },
+                children: resolve_fields(field.children, scope)?,
+                ..field
+            })
+        })
+        .collect()
+}
+
+// Declarations and assignments become `let` statements, which the VM runs in
+// order, binding the name for the statements after them in the same block.
+// Constness and declaration are checked here, as the VM knows neither. As a
+// `let` only binds in its own block, assigning to a name declared in an
+// enclosing block is rejected rather than leaving that binding unchanged.
+fn lower_statements(
+    statements: Vec<Statement>,
+    scope: &HashMap<String, Binding>,
+) -> Result<Vec<Statement>, ParseError> {
+    let mut scope = scope.clone();
+    // The names declared in this block.
+    let mut declared = HashSet::new();
+    let mut lowered = Vec::new();
+    let mut returned = false;
+    for Statement { kind, span } in statements {
+        if returned {
+            return Err(ParseError::Syntax(Diagnostic::new(
+                "unreachable statement after return".to_string(),
+                span,
+            )));
+        }
+        let (binding, value) = match kind {
+            StatementKind::Const(name, _) | StatementKind::Let(name, _)
+                if declared.contains(&name) =>
+            {
+                return Err(ParseError::Syntax(Diagnostic::new(
+                    format!("`{}` is already declared in this block", name),
+                    span,
+                )))
+            }
+            StatementKind::Const(name, value) => (Some((name, Binding::Const)), value),
+            StatementKind::Let(name, value) => (Some((name, Binding::Let)), value),
+            StatementKind::Assign(name, value) => match scope.get(&name) {
+                Some(Binding::Let) if declared.contains(&name) => {
+                    (Some((name, Binding::Let)), value)
+                }
+                Some(Binding::Let) => {
+                    return Err(ParseError::Syntax(Diagnostic::new(
+                        format!(
+                            "cannot assign to `{}`, which is declared outside this block",
+                            name
+                        ),
+                        span,
+                    )))
+                }
+                Some(Binding::Const) => {
+                    return Err(ParseError::Syntax(Diagnostic::new(
+                        format!("cannot assign to constant `{}`", name),
+                        span,
+                    )))
+                }
+                None => {
+                    return Err(ParseError::Syntax(Diagnostic::new(
+                        format!("cannot assign to undeclared `{}`", name),
+                        span,
+                    )))
+                }
+            },
+            StatementKind::Return(value) => {
+                returned = true;
+                (None, value)
+            }
+            StatementKind::Expr(value) => (None, value),
+        };
+        let mut value = resolve(value, &scope)?;
+        if let Some((name, binding)) = binding {
+            declared.insert(name.clone());
+            scope.insert(name.clone(), binding);
+            value = Expr::new(ExprKind::Bind(name, Box::new(value)), span);
+        }
+        lowered.push(Statement {
+            kind: StatementKind::Expr(value),
+            span,
+        });
+    }
+    Ok(lowered)
+}
+
+fn lower<R, T>(statement: Statement, reducer: &R) -> T
+where
+    R: SpanReducer<T>,
+{
+    match statement.kind {
+        StatementKind::Expr(expr) => lower_expr(expr, reducer),
+        _ => unreachable!("declarations are removed by lower_statements"),
+    }
+}
+
+fn get<R, T>(name: String, span: Span, reducer: &R) -> T
+where
+    R: SpanReducer<T>,
+{
+    reducer.node(
+        "get".to_string(),
+        Some(vec![reducer.value(Value::String(name), span)]),
+        span,
+    )
+}
+
+fn lower_expr<R, T>(expr: Expr, reducer: &R) -> T
+where
+    R: SpanReducer<T>,
+{
+    let span = expr.span;
+    match expr.kind {
+        ExprKind::Id(name) => get(name, span, reducer),
+        ExprKind::Value(value) => reducer.value(value, span),
+        ExprKind::Call(callee, args) => {
+            let mut children = vec![lower_expr(*callee, reducer)];
+            children.extend(args.into_iter().map(|arg| lower_expr(arg, reducer)));
+            reducer.node("call".to_string(), Some(children), span)
+        }
+        ExprKind::Operator(name, operator_span, args) => {
+            let mut children = vec![get(name, operator_span, reducer)];
+            children.extend(args.into_iter().map(|arg| lower_expr(arg, reducer)));
+            reducer.node("call".to_string(), Some(children), span)
+        }
+        ExprKind::Logic(name, args) => reducer.node(
+            name,
+            Some(
+                args.into_iter()
+                    .map(|arg| lower_expr(arg, reducer))
+                    .collect(),
+            ),
+            span,
+        ),
+        ExprKind::List(items) => reducer.node(
+            "list".to_string(),
+            Some(
+                items
+                    .into_iter()
+                    .map(|item| lower_expr(item, reducer))
+                    .collect(),
+            ),
+            span,
+        ),
+        ExprKind::Object(entries) => reducer.node(
+            "object".to_string(),
+            Some(
+                entries
+                    .into_iter()
+                    .map(|entry| lower_expr(entry, reducer))
+                    .collect(),
+            ),
+            span,
+        ),
+        ExprKind::Block(name, statements) => reducer.node(
+            name,
+            Some(
+                statements
+                    .into_iter()
+                    .map(|statement| lower(statement, reducer))
+                    .collect(),
+            ),
+            span,
+        ),
+        ExprKind::Query(fields) => reducer.node(
+            "query".to_string(),
+            Some(lower_fields(fields, reducer)),
+            span,
+        ),
+        ExprKind::Bind(name, value) => reducer.node(
+            "let".to_string(),
+            Some(vec![
+                reducer.value(Value::String(name), span),
+                lower_expr(*value, reducer),
+            ]),
+            span,
+        ),
+    }
+}
+
+fn lower_fields<R, T>(fields: Vec<Field>, reducer: &R) -> Vec<T>
+where
+    R: SpanReducer<T>,
+{
+    fields
+        .into_iter()
+        .map(|field| {
+            let (value, value_span) = match field.value {
+                Some(value) => {
+                    let span = value.span;
+                    (lower_expr(value, reducer), span)
+                }
+                None => (
+                    get(field.name.clone(), field.name_span, reducer),
+                    field.name_span,
+                ),
+            };
+            let mut children = vec![
+                reducer.value(Value::String(field.name), field.name_span),
+                reducer.node("f".to_string(), Some(vec![value]), value_span),
+            ];
+            children.extend(lower_fields(field.children, reducer));
+            reducer.node("node".to_string(), Some(children), field.span)
+        })
+        .collect()
+}
+
+// The number of nodes and values `lower` makes of a lowered statement.
+fn statement_nodes(statement: &Statement) -> usize {
+    match &statement.kind {
+        StatementKind::Expr(expr) => expr_nodes(expr),
+        _ => unreachable!("declarations are removed by lower_statements"),
+    }
+}
+
+fn expr_nodes(expr: &Expr) -> usize {
+    let sum = |exprs: &[Expr]| exprs.iter().map(expr_nodes).sum::<usize>();
+    match &expr.kind {
+        ExprKind::Id(_) => 2,
+        ExprKind::Value(_) => 1,
+        ExprKind::Call(callee, args) => 1 + expr_nodes(callee) + sum(args),
+        ExprKind::Operator(_, _, args) => 3 + sum(args),
+        ExprKind::Logic(_, args) => 1 + sum(args),
+        ExprKind::List(items) | ExprKind::Object(items) => 1 + sum(items),
+        ExprKind::Block(_, statements) => 1 + statements.iter().map(statement_nodes).sum::<usize>(),
+        ExprKind::Query(fields) => 1 + field_nodes(fields),
+        ExprKind::Bind(_, value) => 2 + expr_nodes(value),
+    }
+}
+
+fn field_nodes(fields: &[Field]) -> usize {
+    fields
+        .iter()
+        .map(|field| {
+            let value = field.value.as_ref().map_or(2, expr_nodes);
+            3 + value + field_nodes(&field.children)
+        })
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::askcode::{AskCodeOrValue, AskCodeReducer, SpannedAskCodeOrValue};
+    use crate::parse::{parse, parse_askscript, parse_askscript_with_spans};
+    use crate::reduce::{ParseError, ParserOptions};
+
+    fn assert_compiles_to(script: &str, ask_code: &str) {
+        assert_eq!(
+            parse_askscript(script.to_string(), false).unwrap(),
+            parse(ask_code.to_string(), false).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_empty_program() {
+        let repl = parse_askscript("  // nothing here\n".to_string(), false);
+        assert_eq!(repl.err().unwrap(), ParseError::EmptyProgram);
+    }
+
+    #[test]
+    fn test_operators_precedence() {
+        assert_compiles_to(
+            "ask { 2 + 3 * 4 }",
+            "ask(call(get('+'),2,call(get('*'),3,4)))",
+        );
+        assert_compiles_to(
+            "ask { (2 + 3) * -4 }",
+            "ask(call(get('*'),call(get('+'),2,3),-4))",
+        );
+        assert_compiles_to("1 == 2", "call(get('equals'),1,2)");
+        assert_compiles_to(
+            "a || b && !c",
+            "or(get('a'),and(get('b'),call(get('not'),get('c'))))",
+        );
+    }
+
+    #[test]
+    fn test_const_and_return() {
+        assert_compiles_to(
+            "ask { const x = 2 + 3; return x }",
+            "ask(let('x',call(get('+'),2,3)),get('x'))",
+        );
+        assert_compiles_to(
+            "ask {\n  let x = 1\n  x = x + 1\n  return x * x\n}",
+            "ask(let('x',1),let('x',call(get('+'),get('x'),1)),call(get('*'),get('x'),get('x')))",
+        );
+    }
+
+    #[test]
+    fn test_max_nodes_counts_lowered_code() {
+        let script = format!("ask {{ let x = 1; {}return x }}", "x = x + x; ".repeat(30));
+        let limited = |max_nodes| {
+            let options = ParserOptions {
+                max_nodes: Some(max_nodes),
+                ..ParserOptions::default()
+            };
+            super::AskScriptParser::with_options(script.clone(), options, false)
+                .reduce(AskCodeReducer {})
+                .err()
+        };
+        // `ask`, `let('x',1)`, 30 of `let('x',call(get('+'),get('x'),get('x')))`
+        // and `get('x')`.
+        assert_eq!(limited(276), None);
+        assert_eq!(limited(275), Some(ParseError::ExceedMaxNodes(275)));
+    }
+
+    fn error_message(script: &str) -> String {
+        parse_askscript(script.to_string(), false)
+            .unwrap_err()
+            .to_string()
+    }
+
+    #[test]
+    fn test_invalid_bindings() {
+        assert_eq!(
+            error_message("ask { const x = 1; x = 2 }"),
+            "1:20: cannot assign to constant `x`"
+        );
+        assert_eq!(
+            error_message("ask { y = 2 }"),
+            "1:7: cannot assign to undeclared `y`"
+        );
+        assert_eq!(
+            error_message("ask { let n = 0; const f = fun { n = n + 1 }; f() }"),
+            "1:34: cannot assign to `n`, which is declared outside this block"
+        );
+        assert_eq!(
+            error_message("ask { const x = 1; let x = 2 }"),
+            "1:20: `x` is already declared in this block"
+        );
+        assert_eq!(
+            error_message("ask { let x = 1; let x = 2 }"),
+            "1:18: `x` is already declared in this block"
+        );
+        // Shadowing in an inner block declares a new name there.
+        assert!(parse_askscript(
+            "ask { let n = 0; const f = fun { let n = 1; n = 2; return n }; f() }".to_string(),
+            false
+        )
+        .is_ok());
+        assert_eq!(
+            error_message("ask { return 1; 2 }"),
+            "1:17: unreachable statement after return"
+        );
+    }
+
+    #[test]
+    fn test_syntax_errors() {
+        assert_eq!(
+            error_message("ask { max(1 2) }"),
+            "1:13: expected `,` or `)`, found `2`"
+        );
+        assert_eq!(
+            error_message("ask {\n  'abc"),
+            "2:3: expected `'`, found end of input"
+        );
+        assert_eq!(
+            error_message("ask { 1 + }"),
+            "1:11: expected expression, found `}`"
+        );
+        assert_eq!(
+            error_message("ask { 1 # 2 }"),
+            "1:9: unexpected character `#`"
+        );
+    }
+
+    #[test]
+    fn test_string_escapes() {
+        assert_compiles_to(
+            r#"ask { 'it\'s\n' + "\"\u{1F600}\"" }"#,
+            r#"ask(call(get('+'),"it's\n",'"😀"'))"#,
+        );
+        assert_eq!(
+            error_message(r"ask { '\x' }"),
+            "1:8: unknown escape sequence `\\x`"
+        );
+    }
+
+    #[test]
+    fn test_number_literals() {
+        assert_compiles_to(
+            "ask { 1_000 + 0xff - 1.5e3 }",
+            "ask(call(get('-'),call(get('+'),1000,255),1.5e3))",
+        );
+        assert_eq!(
+            error_message("ask { 1..2 }"),
+            "1:7: invalid number literal `1..2`: expected digits, found `.`"
+        );
+    }
+
+    #[test]
+    fn test_object_literals() {
+        assert_compiles_to(
+            "ask { const n = 1; return { a: n + 1, 'b c': [n] } }",
+            "ask(let('n',1),object(list('a',call(get('+'),get('n'),1)),list('b c',list(get('n')))))",
+        );
+        assert_eq!(
+            error_message("ask { { a 1 } }"),
+            "1:11: expected `:`, found `1`"
+        );
+    }
+
+    #[test]
+    fn test_calls_and_methods() {
+        assert_compiles_to(
+            "ask { max([1, 2], 'a':concat('b')) }",
+            "ask(call(get('max'),list(1,2),call(get('concat'),'a','b')))",
+        );
+        assert_compiles_to(
+            "firstName:toLowerCase",
+            "call(get('toLowerCase'),get('firstName'))",
+        );
+    }
+
+    #[test]
+    fn test_fun_and_query() {
+        assert_compiles_to("fun { return 'hi' }", "f('hi')");
+        assert_compiles_to(
+            "ask { query { firstName friends { id } name: firstName:toUpperCase } }",
+            "ask(query(node('firstName',f(get('firstName'))),node('friends',f(get('friends')),node('id',f(get('id')))),node('name',f(call(get('toUpperCase'),get('firstName'))))))",
+        );
+    }
+
+    #[test]
+    fn test_custom_reducer_is_used() {
+        let mut parser = super::AskScriptParser::new("ask { 1 }".to_string(), None, false);
+        let code: AskCodeOrValue = parser.reduce(AskCodeReducer {}).unwrap();
+        assert_eq!(code, parse("ask(1)".to_string(), false).unwrap());
+    }
+
+    #[test]
+    fn test_spans() {
+        let script = "ask {\n  const x = max(1, 2)\n  return x + 3\n}";
+        let code = parse_askscript_with_spans(script.to_string(), false).unwrap();
+        assert_eq!(code.span().source(script), script);
+        let mut statements = match code {
+            SpannedAskCodeOrValue::AskCode(ask) => ask.params.unwrap(),
+            _ => panic!("expecting ask"),
+        };
+        assert_eq!(statements[0].span().source(script), "const x = max(1, 2)");
+        let call = statements.remove(1);
+        assert_eq!(call.span().source(script), "x + 3");
+        assert_eq!((call.span().start.line, call.span().start.column), (3, 10));
+        let params = match call {
+            SpannedAskCodeOrValue::AskCode(call) => call.params.unwrap(),
+            _ => panic!("expecting call"),
+        };
+        assert_eq!(params[0].span().source(script), "+");
+        assert_eq!(params[1].span().source(script), "x");
+        assert_eq!(params[2].span().source(script), "3");
+    }
+}
@@ -0,0 +1,575 @@
+//! Versioned binary encoding of AskCode trees, for caching compiled programs
+//! and loading them without parsing.
+//!
+//! An encoded program is the magic bytes `ASKC`, a format version byte, the
+//! identifier table and then the tree as a stream of tagged items:
+//!
+//! - identifier table: the count, then each name as length and UTF-8 bytes;
+//!   nodes refer to names by their index in the table
+//! - `0x00 name`: identifier without params
+//! - `0x01 name count params...`: node
+//! - `0x10` null, `0x11` false, `0x12` true
+//! - `0x13 int`: `Int`, zigzag encoded
+//! - `0x14 f64`: `Float`, 8 bytes little endian
+//! - `0x15 text`: `Number` literal, as written
+//! - `0x16 text`: `String`
+//! - `0x17 count values...`: `List`
+//! - `0x18 count (text value)...`: `Object`
+//! - `0x19 text`: `BigInt`, in decimal
+//! - `0x1a text`: `Decimal`, in decimal
+//! - `0x1b seconds nanoseconds`: `DateTime`, the seconds since the Unix
+//!   epoch zigzag encoded, then the nanoseconds past them
+//! - `0x1c text`: `Duration`, in ISO-8601
+//! - `0x1d length bytes...`: `Bytes`
+//!
+//! All counts, lengths and indices are unsigned LEB128 varints.
+
+use crate::askcode::{AskCode, AskCodeOrValue};
+use crate::temporal::{format_duration, parse_duration, DateTime};
+use crate::value::{BigDecimal, Number, Object, Value};
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::fmt;
+use std::str::FromStr;
+
+const MAGIC: &[u8] = b"ASKC";
+pub const BINARY_VERSION: u8 = 1;
+// Deeper trees are rejected rather than overflowing the stack.
+const MAX_DEPTH: usize = 1024;
+
+const ID: u8 = 0x00;
+const NODE: u8 = 0x01;
+const NULL: u8 = 0x10;
+const FALSE: u8 = 0x11;
+const TRUE: u8 = 0x12;
+const INT: u8 = 0x13;
+const FLOAT: u8 = 0x14;
+const NUMBER: u8 = 0x15;
+const STRING: u8 = 0x16;
+const LIST: u8 = 0x17;
+const OBJECT: u8 = 0x18;
+const BIGINT: u8 = 0x19;
+const DECIMAL: u8 = 0x1a;
+const DATETIME: u8 = 0x1b;
+const DURATION: u8 = 0x1c;
+const BYTES: u8 = 0x1d;
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum DecodeError {
+    BadMagic,
+    UnsupportedVersion(u8),
+    UnexpectedEnd,
+    InvalidTag(u8),
+    InvalidIdentifier(usize),
+    InvalidUtf8,
+    InvalidNumber(String),
+    InvalidTemporal(String),
+    InvalidVarint,
+    TooDeep,
+    TrailingBytes,
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DecodeError::BadMagic => write!(f, "not an encoded AskCode program"),
+            DecodeError::UnsupportedVersion(version) => {
+                write!(f, "unsupported binary format version {}", version)
+            }
+            DecodeError::UnexpectedEnd => write!(f, "unexpected end of input"),
+            DecodeError::InvalidTag(tag) => write!(f, "invalid tag {:#04x}", tag),
+            DecodeError::InvalidIdentifier(index) => {
+                write!(f, "identifier {} is not in the table", index)
+            }
+            DecodeError::InvalidUtf8 => write!(f, "invalid UTF-8 in string"),
+            DecodeError::InvalidNumber(text) => write!(f, "invalid number `{}`", text),
+            DecodeError::InvalidTemporal(text) => {
+                write!(f, "invalid date-time or duration `{}`", text)
+            }
+            DecodeError::InvalidVarint => write!(f, "varint is too large"),
+            DecodeError::TooDeep => write!(f, "tree nested more than {} levels", MAX_DEPTH),
+            DecodeError::TrailingBytes => write!(f, "trailing bytes after the program"),
+        }
+    }
+}
+
+pub fn to_binary(code: &AskCodeOrValue) -> Vec<u8> {
+    let mut identifiers = Vec::new();
+    let mut indices = HashMap::new();
+    collect_identifiers(code, &mut identifiers, &mut indices);
+    let mut out = MAGIC.to_vec();
+    out.push(BINARY_VERSION);
+    write_varint(&mut out, identifiers.len());
+    for name in &identifiers {
+        write_text(&mut out, name);
+    }
+    write_code(&mut out, code, &indices);
+    out
+}
+
+pub fn from_binary(bytes: &[u8]) -> Result<AskCodeOrValue, DecodeError> {
+    if !bytes.starts_with(MAGIC) {
+        return Err(DecodeError::BadMagic);
+    }
+    let mut reader = Reader {
+        bytes,
+        index: MAGIC.len(),
+        identifiers: Vec::new(),
+    };
+    let version = reader.byte()?;
+    if version != BINARY_VERSION {
+        return Err(DecodeError::UnsupportedVersion(version));
+    }
+    for _ in 0..reader.varint()? {
+        let name = reader.text()?;
+        reader.identifiers.push(name);
+    }
+    let code = reader.code(0)?;
+    if reader.index != bytes.len() {
+        return Err(DecodeError::TrailingBytes);
+    }
+    Ok(code)
+}
+
+fn collect_identifiers<'a>(
+    code: &'a AskCodeOrValue,
+    identifiers: &mut Vec<&'a str>,
+    indices: &mut HashMap<&'a str, usize>,
+) {
+    if let AskCodeOrValue::AskCode(askcode) = code {
+        if !indices.contains_key(askcode.name.as_str()) {
+            indices.insert(&askcode.name, identifiers.len());
+            identifiers.push(&askcode.name);
+        }
+        for param in askcode.params.iter().flatten() {
+            collect_identifiers(param, identifiers, indices);
+        }
+    }
+}
+
+fn write_code(out: &mut Vec<u8>, code: &AskCodeOrValue, indices: &HashMap<&str, usize>) {
+    match code {
+        AskCodeOrValue::Value(value) => write_value(out, value),
+        AskCodeOrValue::AskCode(askcode) => {
+            let index = indices[askcode.name.as_str()];
+            match &askcode.params {
+                None => {
+                    out.push(ID);
+                    write_varint(out, index);
+                }
+                Some(params) => {
+                    out.push(NODE);
+                    write_varint(out, index);
+                    write_varint(out, params.len());
+                    for param in params {
+                        write_code(out, param, indices);
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn write_value(out: &mut Vec<u8>, value: &Value) {
+    match value {
+        Value::Null => out.push(NULL),
+        Value::Boolean(false) => out.push(FALSE),
+        Value::Boolean(true) => out.push(TRUE),
+        Value::Int(int) => {
+            out.push(INT);
+            write_zigzag(out, *int);
+        }
+        Value::Float(float) => {
+            out.push(FLOAT);
+            out.extend_from_slice(&float.to_le_bytes());
+        }
+        Value::BigInt(int) => {
+            out.push(BIGINT);
+            write_text(out, &int.to_string());
+        }
+        Value::Decimal(decimal) => {
+            out.push(DECIMAL);
+            write_text(out, &decimal.to_string());
+        }
+        Value::DateTime(date_time) => {
+            out.push(DATETIME);
+            write_zigzag(out, date_time.timestamp());
+            write_varint(out, date_time.timestamp_subsec_nanos() as usize);
+        }
+        Value::Duration(duration) => {
+            out.push(DURATION);
+            write_text(out, &format_duration(duration));
+        }
+        Value::Number(Number(number)) => {
+            out.push(NUMBER);
+            write_text(out, number);
+        }
+        Value::String(string) => {
+            out.push(STRING);
+            write_text(out, string);
+        }
+        Value::Bytes(bytes) => {
+            out.push(BYTES);
+            write_varint(out, bytes.len());
+            out.extend_from_slice(bytes);
+        }
+        Value::List(list) => {
+            out.push(LIST);
+            write_varint(out, list.len());
+            for value in list {
+                write_value(out, value);
+            }
+        }
+        Value::Object(object) => {
+            out.push(OBJECT);
+            write_varint(out, object.len());
+            for (key, value) in object {
+                write_text(out, key);
+                write_value(out, value);
+            }
+        }
+    }
+}
+
+fn write_varint(out: &mut Vec<u8>, value: usize) {
+    write_varint64(out, value as u64);
+}
+
+fn write_varint64(out: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        out.push(value as u8 | 0x80);
+        value >>= 7;
+    }
+    out.push(value as u8);
+}
+
+fn write_zigzag(out: &mut Vec<u8>, value: i64) {
+    write_varint64(out, ((value << 1) ^ (value >> 63)) as u64);
+}
+
+fn write_text(out: &mut Vec<u8>, text: &str) {
+    write_varint(out, text.len());
+    out.extend_from_slice(text.as_bytes());
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    index: usize,
+    identifiers: Vec<String>,
+}
+
+impl Reader<'_> {
+    fn code(&mut self, depth: usize) -> Result<AskCodeOrValue, DecodeError> {
+        if depth > MAX_DEPTH {
+            return Err(DecodeError::TooDeep);
+        }
+        match self.peek()? {
+            ID => {
+                self.index += 1;
+                let name = self.identifier()?;
+                Ok(AskCodeOrValue::AskCode(AskCode::new(name, None)))
+            }
+            NODE => {
+                self.index += 1;
+                let name = self.identifier()?;
+                let count = self.varint()?;
+                let mut params = Vec::with_capacity(self.capacity(count));
+                for _ in 0..count {
+                    params.push(self.code(depth + 1)?);
+                }
+                Ok(AskCodeOrValue::AskCode(AskCode::new(name, Some(params))))
+            }
+            _ => Ok(AskCodeOrValue::Value(self.value(depth)?)),
+        }
+    }
+
+    fn value(&mut self, depth: usize) -> Result<Value, DecodeError> {
+        if depth > MAX_DEPTH {
+            return Err(DecodeError::TooDeep);
+        }
+        Ok(match self.byte()? {
+            LIST => {
+                let count = self.varint()?;
+                let mut list = Vec::with_capacity(self.capacity(count));
+                for _ in 0..count {
+                    list.push(self.value(depth + 1)?);
+                }
+                Value::List(list)
+            }
+            OBJECT => self.object(depth)?,
+            tag => self.scalar(tag)?,
+        })
+    }
+
+    // Kept out of `value` for the same reason as `scalar`: a local `Object`
+    // would grow its frame.
+    #[inline(never)]
+    fn object(&mut self, depth: usize) -> Result<Value, DecodeError> {
+        let mut object = Object::new();
+        for _ in 0..self.varint()? {
+            let key = self.text()?;
+            object.insert(key, self.value(depth + 1)?);
+        }
+        Ok(Value::Object(object))
+    }
+
+    // Kept out of `value`, whose stack frame bounds how deep a tree can be
+    // decoded.
+    fn scalar(&mut self, tag: u8) -> Result<Value, DecodeError> {
+        Ok(match tag {
+            NULL => Value::Null,
+            FALSE => Value::Boolean(false),
+            TRUE => Value::Boolean(true),
+            INT => Value::Int(self.zigzag()?),
+            FLOAT => {
+                let mut bytes = [0; 8];
+                bytes.copy_from_slice(self.take(8)?);
+                Value::Float(f64::from_le_bytes(bytes))
+            }
+            BIGINT => Value::BigInt(self.number()?),
+            DECIMAL => Value::Decimal(self.decimal()?),
+            DATETIME => {
+                let seconds = self.zigzag()?;
+                let nanos = self.varint64()?;
+                let date_time = u32::try_from(nanos)
+                    .ok()
+                    .and_then(|nanos| DateTime::from_timestamp(seconds, nanos));
+                Value::DateTime(date_time.ok_or_else(|| {
+                    DecodeError::InvalidTemporal(format!("{}s {}ns", seconds, nanos))
+                })?)
+            }
+            DURATION => {
+                let text = self.text()?;
+                Value::Duration(parse_duration(&text).ok_or(DecodeError::InvalidTemporal(text))?)
+            }
+            NUMBER => Value::Number(Number(self.text()?)),
+            STRING => Value::String(self.text()?),
+            BYTES => {
+                let len = self.varint()?;
+                Value::Bytes(self.take(len)?.to_vec())
+            }
+            tag => return Err(DecodeError::InvalidTag(tag)),
+        })
+    }
+
+    fn identifier(&mut self) -> Result<String, DecodeError> {
+        let index = self.varint()?;
+        match self.identifiers.get(index) {
+            Some(name) => Ok(name.clone()),
+            None => Err(DecodeError::InvalidIdentifier(index)),
+        }
+    }
+
+    fn text(&mut self) -> Result<String, DecodeError> {
+        let len = self.varint()?;
+        let bytes = self.take(len)?;
+        String::from_utf8(bytes.to_vec()).map_err(|_| DecodeError::InvalidUtf8)
+    }
+
+    fn number<T: FromStr>(&mut self) -> Result<T, DecodeError> {
+        let text = self.text()?;
+        text.parse().map_err(|_| DecodeError::InvalidNumber(text))
+    }
+
+    // Held to the range of literals, as a decimal with a huge exponent makes
+    // later arithmetic on it run away.
+    fn decimal(&mut self) -> Result<BigDecimal, DecodeError> {
+        let text = self.text()?;
+        if Number(text.clone()).to_value().is_err() {
+            return Err(DecodeError::InvalidNumber(text));
+        }
+        text.parse().map_err(|_| DecodeError::InvalidNumber(text))
+    }
+
+    fn varint(&mut self) -> Result<usize, DecodeError> {
+        usize::try_from(self.varint64()?).map_err(|_| DecodeError::InvalidVarint)
+    }
+
+    fn zigzag(&mut self) -> Result<i64, DecodeError> {
+        let zigzag = self.varint64()?;
+        Ok(((zigzag >> 1) as i64) ^ -((zigzag & 1) as i64))
+    }
+
+    fn varint64(&mut self) -> Result<u64, DecodeError> {
+        let mut value: u64 = 0;
+        let mut shift = 0;
+        loop {
+            let byte = self.byte()?;
+            if shift >= u64::BITS {
+                return Err(DecodeError::InvalidVarint);
+            }
+            value |= ((byte & 0x7f) as u64) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+            shift += 7;
+        }
+    }
+
+    // Every item takes at least a byte, so a count larger than what is left
+    // is bogus and must not be allocated for up front.
+    fn capacity(&self, count: usize) -> usize {
+        count.min(self.bytes.len() - self.index)
+    }
+
+    fn peek(&self) -> Result<u8, DecodeError> {
+        self.bytes
+            .get(self.index)
+            .copied()
+            .ok_or(DecodeError::UnexpectedEnd)
+    }
+
+    fn byte(&mut self) -> Result<u8, DecodeError> {
+        let byte = self.peek()?;
+        self.index += 1;
+        Ok(byte)
+    }
+
+    fn take(&mut self, len: usize) -> Result<&[u8], DecodeError> {
+        if len > self.bytes.len() - self.index {
+            return Err(DecodeError::UnexpectedEnd);
+        }
+        let bytes = &self.bytes[self.index..self.index + len];
+        self.index += len;
+        Ok(bytes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse::parse;
+    use crate::temporal::{TimeDelta, Utc};
+    use proptest::prelude::*;
+
+    #[test]
+    fn test_encoding() {
+        let code = parse("f(x, x, -1.5, 'é')".to_string(), false).unwrap();
+        let bytes = to_binary(&code);
+        assert_eq!(
+            bytes,
+            [
+                b"ASKC".as_ref(),
+                &[BINARY_VERSION, 2, 1, b'f', 1, b'x'],
+                &[NODE, 0, 4, ID, 1, ID, 1],
+                &[NUMBER, 4, b'-', b'1', b'.', b'5'],
+                &[STRING, 2, 0xc3, 0xa9],
+            ]
+            .concat()
+        );
+        assert_eq!(from_binary(&bytes), Ok(code));
+    }
+
+    #[test]
+    fn test_wide_numbers() {
+        let values = Value::List(vec![
+            Value::Int(i64::MIN),
+            Value::Int(i64::MAX),
+            Value::Int(-1),
+            Value::Float(0.1),
+            Value::Float(f64::MAX),
+            Value::BigInt("-170141183460469231731687303715884105729".parse().unwrap()),
+            Value::Decimal("0.10000000000000000001".parse().unwrap()),
+        ]);
+        let code = AskCodeOrValue::Value(values);
+        assert_eq!(from_binary(&to_binary(&code)), Ok(code));
+    }
+
+    #[test]
+    fn test_decode_errors() {
+        let bytes = to_binary(&parse("f(x)".to_string(), false).unwrap());
+        assert_eq!(from_binary(b"{}"), Err(DecodeError::BadMagic));
+        assert_eq!(
+            from_binary(b"ASKC\x07"),
+            Err(DecodeError::UnsupportedVersion(7))
+        );
+        assert_eq!(
+            from_binary(&bytes[..bytes.len() - 1]),
+            Err(DecodeError::UnexpectedEnd)
+        );
+        assert_eq!(
+            from_binary(&[&bytes[..], &[0]].concat()),
+            Err(DecodeError::TrailingBytes)
+        );
+        assert_eq!(
+            from_binary(b"ASKC\x01\x00\x00\x03"),
+            Err(DecodeError::InvalidIdentifier(3))
+        );
+        assert_eq!(
+            from_binary(b"ASKC\x01\x00\xff"),
+            Err(DecodeError::InvalidTag(0xff))
+        );
+        assert_eq!(
+            from_binary(b"ASKC\x01\x00\x19\x01x"),
+            Err(DecodeError::InvalidNumber("x".to_string()))
+        );
+        assert_eq!(
+            from_binary(b"ASKC\x01\x00\x1a\x0a1e99999999"),
+            Err(DecodeError::InvalidNumber("1e99999999".to_string()))
+        );
+        assert_eq!(
+            from_binary(b"ASKC\x01\x00\x1b\x00\x80\xa8\xd6\xb9\x07"),
+            Err(DecodeError::InvalidTemporal("0s 2000000000ns".to_string()))
+        );
+        assert_eq!(
+            from_binary(b"ASKC\x01\x00\x1c\x02P1"),
+            Err(DecodeError::InvalidTemporal("P1".to_string()))
+        );
+        let deep = [b"ASKC\x01\x00".as_ref(), &[LIST, 1].repeat(2000), &[NULL]].concat();
+        assert_eq!(from_binary(&deep), Err(DecodeError::TooDeep));
+    }
+
+    fn value() -> impl Strategy<Value = Value> {
+        let leaf = prop_oneof![
+            Just(Value::Null),
+            any::<bool>().prop_map(Value::Boolean),
+            any::<i64>().prop_map(Value::Int),
+            any::<f64>().prop_map(Value::Float),
+            any::<i128>().prop_map(|int| Value::BigInt(int.into())),
+            (any::<i64>(), -100i64..100)
+                .prop_map(|(digits, scale)| Value::Decimal(BigDecimal::new(digits.into(), scale))),
+            any::<String>().prop_map(|number| Value::Number(Number(number))),
+            (
+                DateTime::<Utc>::MIN_UTC.timestamp()..=DateTime::<Utc>::MAX_UTC.timestamp(),
+                0u32..1_000_000_000
+            )
+                .prop_map(|(seconds, nanos)| Value::DateTime(
+                    DateTime::from_timestamp(seconds, nanos).unwrap()
+                )),
+            any::<i64>().prop_map(|nanos| Value::Duration(TimeDelta::nanoseconds(nanos))),
+            any::<String>().prop_map(Value::String),
+            any::<Vec<u8>>().prop_map(Value::Bytes),
+        ];
+        leaf.prop_recursive(3, 32, 5, |inner| {
+            prop_oneof![
+                prop::collection::vec(inner.clone(), 0..5).prop_map(Value::List),
+                prop::collection::vec((any::<String>(), inner), 0..5)
+                    .prop_map(|entries| Value::Object(entries.into_iter().collect())),
+            ]
+        })
+    }
+
+    fn ask_code() -> impl Strategy<Value = AskCodeOrValue> {
+        let leaf = prop_oneof![
+            value().prop_map(AskCodeOrValue::Value),
+            any::<String>().prop_map(|name| AskCodeOrValue::AskCode(AskCode::new(name, None))),
+        ];
+        leaf.prop_recursive(4, 32, 6, |inner| {
+            ("[a-c]", prop::collection::vec(inner, 0..6)).prop_map(|(name, params)| {
+                AskCodeOrValue::AskCode(AskCode::new(name, Some(params)))
+            })
+        })
+    }
+
+    proptest! {
+        #[test]
+        fn prop_round_trips(code in ask_code()) {
+            prop_assert_eq!(from_binary(&to_binary(&code)), Ok(code));
+        }
+
+        #[test]
+        fn prop_decodes_garbage_without_panicking(bytes in prop::collection::vec(any::<u8>(), 0..64)) {
+            let _ = from_binary(&[b"ASKC\x01".as_ref(), &bytes].concat());
+        }
+    }
+}
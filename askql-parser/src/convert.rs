@@ -0,0 +1,772 @@
+//! Conversions between Rust data and `Value`s, for exposing typed data to
+//! queries and reading resource arguments.
+//!
+//! `IntoValue` and `FromValue` are implemented for Rust's primitives,
+//! `String`, big numbers, date-times and durations, `Option`, `Box`, `Vec`,
+//! string-keyed maps and tuples, and the `askql-macros` crate derives them
+//! for structs and enums. Structs become objects, tuple structs lists (or
+//! their only field), unit structs null, unit variants their name and other
+//! variants an object with their name as the only key. Byte strings convert
+//! through `Bytes`, and types that implement serde's traits through `Serde`.
+//!
+//! ```
+//! use askql_parser::{FromValue, IntoValue, Value};
+//!
+//! let value = vec![Some(1), None].into_value();
+//! assert_eq!(value, Value::List(vec![Value::Int(1), Value::Null]));
+//! assert_eq!(Vec::<Option<i64>>::from_value(value), Ok(vec![Some(1), None]));
+//! assert_eq!(
+//!     i32::from_value(Value::String("1".to_string())).unwrap_err().to_string(),
+//!     "expected i32, found string"
+//! );
+//! ```
+
+use crate::temporal::{parse_date_time, parse_duration, DateTime, TimeDelta, Utc};
+use crate::value::{BigDecimal, BigInt, IndexMap, Object, Value};
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use num_traits::{FromPrimitive, ToPrimitive};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use std::convert::TryFrom;
+use std::fmt;
+use std::hash::BuildHasher;
+use std::iter::FromIterator;
+
+pub trait IntoValue {
+    fn into_value(self) -> Value;
+}
+
+pub trait FromValue: Sized {
+    fn from_value(value: Value) -> Result<Self, FromValueError>;
+}
+
+/// `IntoValue` for types whose conversion can fail, such as `Serde`.
+pub trait TryIntoValue {
+    type Error;
+
+    fn try_into_value(self) -> Result<Value, Self::Error>;
+}
+
+/// Why a `Value` could not be converted, and where in it.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct FromValueError {
+    message: String,
+    path: String,
+}
+
+impl FromValueError {
+    pub fn new(message: impl Into<String>) -> Self {
+        FromValueError {
+            message: message.into(),
+            path: String::new(),
+        }
+    }
+
+    /// The value was of the wrong type or out of range.
+    pub fn expected(expected: &str, found: &Value) -> Self {
+        FromValueError::new(format!("expected {}, found {}", expected, kind(found)))
+    }
+
+    pub fn missing_field(key: &str) -> Self {
+        FromValueError::new(format!("missing field `{}`", key))
+    }
+
+    /// Places the error inside the field `key` of an object.
+    pub fn at_key(mut self, key: &str) -> Self {
+        self.path = format!(".{}{}", key, self.path);
+        self
+    }
+
+    /// Places the error inside the element `index` of a list.
+    pub fn at_index(mut self, index: usize) -> Self {
+        self.path = format!("[{}]{}", index, self.path);
+        self
+    }
+
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    /// Where the error is, as in `friends[2].id`; empty for the value itself.
+    pub fn path(&self) -> &str {
+        self.path.trim_start_matches('.')
+    }
+}
+
+impl fmt::Display for FromValueError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.path.is_empty() {
+            write!(f, "{}", self.message)
+        } else {
+            write!(f, "{} at `{}`", self.message, self.path())
+        }
+    }
+}
+
+impl std::error::Error for FromValueError {}
+
+fn kind(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Boolean(_) => "boolean",
+        Value::Int(_) => "int",
+        Value::Float(_) => "float",
+        Value::BigInt(_) => "bigint",
+        Value::Decimal(_) => "decimal",
+        Value::Number(_) => "number",
+        Value::DateTime(_) => "datetime",
+        Value::Duration(_) => "duration",
+        Value::String(_) => "string",
+        Value::Bytes(_) => "bytes",
+        Value::List(_) => "list",
+        Value::Object(_) => "object",
+    }
+}
+
+/// Takes the field `key` out of an object and converts it, as derived
+/// `FromValue` impls do. A missing field reads as null, so `Option` fields
+/// may be left out.
+pub fn take_field<T: FromValue>(object: &mut Object, key: &str) -> Result<T, FromValueError> {
+    match object.swap_remove(key) {
+        Some(value) => T::from_value(value).map_err(|error| error.at_key(key)),
+        None => T::from_value(Value::Null).map_err(|_| FromValueError::missing_field(key)),
+    }
+}
+
+/// Converts the element `index` of a list, placing any error at it.
+pub fn from_element<T: FromValue>(value: Value, index: usize) -> Result<T, FromValueError> {
+    T::from_value(value).map_err(|error| error.at_index(index))
+}
+
+impl IntoValue for Value {
+    fn into_value(self) -> Value {
+        self
+    }
+}
+
+impl FromValue for Value {
+    fn from_value(value: Value) -> Result<Self, FromValueError> {
+        Ok(value)
+    }
+}
+
+impl IntoValue for () {
+    fn into_value(self) -> Value {
+        Value::Null
+    }
+}
+
+impl FromValue for () {
+    fn from_value(value: Value) -> Result<Self, FromValueError> {
+        match value {
+            Value::Null => Ok(()),
+            other => Err(FromValueError::expected("null", &other)),
+        }
+    }
+}
+
+impl IntoValue for bool {
+    fn into_value(self) -> Value {
+        Value::Boolean(self)
+    }
+}
+
+impl FromValue for bool {
+    fn from_value(value: Value) -> Result<Self, FromValueError> {
+        match value {
+            Value::Boolean(boolean) => Ok(boolean),
+            other => Err(FromValueError::expected("boolean", &other)),
+        }
+    }
+}
+
+impl IntoValue for String {
+    fn into_value(self) -> Value {
+        Value::String(self)
+    }
+}
+
+impl IntoValue for &str {
+    fn into_value(self) -> Value {
+        Value::String(self.to_string())
+    }
+}
+
+impl FromValue for String {
+    fn from_value(value: Value) -> Result<Self, FromValueError> {
+        match value {
+            Value::String(string) => Ok(string),
+            other => Err(FromValueError::expected("string", &other)),
+        }
+    }
+}
+
+// The exact integer a numeric value holds, if it holds one in the i128 range.
+fn integer(value: &Value) -> Option<i128> {
+    match value {
+        Value::Int(int) => Some(i128::from(*int)),
+        Value::BigInt(int) => int.to_i128(),
+        Value::Float(float) if float.fract() == 0.0 => float.to_i128(),
+        Value::Decimal(decimal) if decimal.is_integer() => decimal.to_i128(),
+        Value::Number(number) => i128::try_from(number).ok(),
+        _ => None,
+    }
+}
+
+macro_rules! impl_value_for_int {
+    ($($int:ty),*) => {$(
+        impl IntoValue for $int {
+            fn into_value(self) -> Value {
+                match i64::try_from(self) {
+                    Ok(int) => Value::Int(int),
+                    Err(_) => Value::BigInt(BigInt::from(self)),
+                }
+            }
+        }
+
+        /// Accepts any number that is a whole number in range.
+        impl FromValue for $int {
+            fn from_value(value: Value) -> Result<Self, FromValueError> {
+                integer(&value)
+                    .and_then(|int| <$int>::try_from(int).ok())
+                    .ok_or_else(|| FromValueError::expected(stringify!($int), &value))
+            }
+        }
+    )*};
+}
+
+impl_value_for_int!(i8, i16, i32, i64, i128, isize, u8, u16, u32, u64, u128, usize);
+
+impl IntoValue for f64 {
+    fn into_value(self) -> Value {
+        Value::Float(self)
+    }
+}
+
+impl IntoValue for f32 {
+    fn into_value(self) -> Value {
+        Value::Float(self.into())
+    }
+}
+
+// The nearest float to a numeric value.
+fn float(value: &Value) -> Option<f64> {
+    match value {
+        Value::Int(int) => Some(*int as f64),
+        Value::Float(float) => Some(*float),
+        Value::BigInt(int) => int.to_f64(),
+        Value::Decimal(decimal) => decimal.to_f64(),
+        Value::Number(number) => number.to_float().ok(),
+        _ => None,
+    }
+}
+
+/// Accepts any number, rounding to the nearest float.
+impl FromValue for f64 {
+    fn from_value(value: Value) -> Result<Self, FromValueError> {
+        float(&value).ok_or_else(|| FromValueError::expected("f64", &value))
+    }
+}
+
+/// Accepts any number that does not round to an infinity as an `f32`, unless
+/// it is one already.
+impl FromValue for f32 {
+    fn from_value(value: Value) -> Result<Self, FromValueError> {
+        float(&value)
+            .map(|float| (float, float as f32))
+            .filter(|(float, single)| single.is_finite() || !float.is_finite())
+            .map(|(_, single)| single)
+            .ok_or_else(|| FromValueError::expected("f32", &value))
+    }
+}
+
+impl IntoValue for BigInt {
+    fn into_value(self) -> Value {
+        Value::BigInt(self)
+    }
+}
+
+/// Accepts any number that is a whole number, as the integer types do.
+impl FromValue for BigInt {
+    fn from_value(value: Value) -> Result<Self, FromValueError> {
+        let int = match &value {
+            Value::Int(int) => Some(BigInt::from(*int)),
+            Value::BigInt(int) => Some(int.clone()),
+            Value::Float(float) if float.fract() == 0.0 => BigInt::from_f64(*float),
+            Value::Decimal(decimal) if decimal.is_integer() => {
+                Some(decimal.with_scale(0).into_bigint_and_exponent().0)
+            }
+            Value::Number(number) => number
+                .to_value()
+                .ok()
+                .and_then(|value| BigInt::from_value(value).ok()),
+            _ => None,
+        };
+        int.ok_or_else(|| FromValueError::expected("bigint", &value))
+    }
+}
+
+impl IntoValue for BigDecimal {
+    fn into_value(self) -> Value {
+        Value::Decimal(self)
+    }
+}
+
+/// Accepts any finite number; a `Float` reads as the shortest decimal that
+/// reads back as it. Number literals are held to `Number::to_value`'s range.
+impl FromValue for BigDecimal {
+    fn from_value(value: Value) -> Result<Self, FromValueError> {
+        let decimal = match &value {
+            Value::Int(int) => Some(BigDecimal::from(*int)),
+            Value::BigInt(int) => Some(BigDecimal::from(int.clone())),
+            Value::Decimal(decimal) => Some(decimal.clone()),
+            Value::Float(float) if float.is_finite() => float.to_string().parse().ok(),
+            Value::Number(number) if number.to_value().is_ok() => number.0.parse().ok(),
+            _ => None,
+        };
+        decimal.ok_or_else(|| FromValueError::expected("decimal", &value))
+    }
+}
+
+impl IntoValue for DateTime<Utc> {
+    fn into_value(self) -> Value {
+        Value::DateTime(self)
+    }
+}
+
+/// Also accepts ISO-8601 strings, which is how JSON carries date-times.
+impl FromValue for DateTime<Utc> {
+    fn from_value(value: Value) -> Result<Self, FromValueError> {
+        match value {
+            Value::DateTime(date_time) => Ok(date_time),
+            Value::String(text) => parse_date_time(&text)
+                .ok_or_else(|| FromValueError::expected("datetime", &Value::String(text))),
+            other => Err(FromValueError::expected("datetime", &other)),
+        }
+    }
+}
+
+impl IntoValue for TimeDelta {
+    fn into_value(self) -> Value {
+        Value::Duration(self)
+    }
+}
+
+/// Also accepts ISO-8601 strings, which is how JSON carries durations.
+impl FromValue for TimeDelta {
+    fn from_value(value: Value) -> Result<Self, FromValueError> {
+        match value {
+            Value::Duration(duration) => Ok(duration),
+            Value::String(text) => parse_duration(&text)
+                .ok_or_else(|| FromValueError::expected("duration", &Value::String(text))),
+            other => Err(FromValueError::expected("duration", &other)),
+        }
+    }
+}
+
+/// Converts a byte string to and from `Value::Bytes`; a bare `Vec<u8>`
+/// converts to a list of integers.
+///
+/// ```
+/// use askql_parser::{Bytes, FromValue, IntoValue, Value};
+///
+/// assert_eq!(Bytes(b"hi".to_vec()).into_value(), Value::Bytes(b"hi".to_vec()));
+/// assert_eq!(Bytes::from_value(Value::String("aGk=".to_string())), Ok(Bytes(b"hi".to_vec())));
+/// ```
+#[derive(Debug, Clone, Default, Eq, PartialEq, Hash)]
+pub struct Bytes(pub Vec<u8>);
+
+impl IntoValue for Bytes {
+    fn into_value(self) -> Value {
+        Value::Bytes(self.0)
+    }
+}
+
+/// Also accepts base64 strings, which is how JSON carries bytes.
+impl FromValue for Bytes {
+    fn from_value(value: Value) -> Result<Self, FromValueError> {
+        match value {
+            Value::Bytes(bytes) => Ok(Bytes(bytes)),
+            Value::String(text) => match BASE64.decode(&text) {
+                Ok(bytes) => Ok(Bytes(bytes)),
+                Err(_) => Err(FromValueError::expected("bytes", &Value::String(text))),
+            },
+            other => Err(FromValueError::expected("bytes", &other)),
+        }
+    }
+}
+
+impl<T: IntoValue> IntoValue for Option<T> {
+    fn into_value(self) -> Value {
+        match self {
+            Some(value) => value.into_value(),
+            None => Value::Null,
+        }
+    }
+}
+
+/// Null reads as `None`.
+impl<T: FromValue> FromValue for Option<T> {
+    fn from_value(value: Value) -> Result<Self, FromValueError> {
+        match value {
+            Value::Null => Ok(None),
+            value => T::from_value(value).map(Some),
+        }
+    }
+}
+
+impl<T: IntoValue> IntoValue for Box<T> {
+    fn into_value(self) -> Value {
+        (*self).into_value()
+    }
+}
+
+impl<T: FromValue> FromValue for Box<T> {
+    fn from_value(value: Value) -> Result<Self, FromValueError> {
+        T::from_value(value).map(Box::new)
+    }
+}
+
+impl<T: IntoValue> IntoValue for Vec<T> {
+    fn into_value(self) -> Value {
+        Value::List(self.into_iter().map(IntoValue::into_value).collect())
+    }
+}
+
+impl<T: FromValue> FromValue for Vec<T> {
+    fn from_value(value: Value) -> Result<Self, FromValueError> {
+        match value {
+            Value::List(list) => list
+                .into_iter()
+                .enumerate()
+                .map(|(index, value)| from_element(value, index))
+                .collect(),
+            other => Err(FromValueError::expected("list", &other)),
+        }
+    }
+}
+
+impl<T: IntoValue> IntoValue for BTreeMap<String, T> {
+    fn into_value(self) -> Value {
+        Value::Object(
+            self.into_iter()
+                .map(|(key, value)| (key, value.into_value()))
+                .collect(),
+        )
+    }
+}
+
+impl<T: FromValue> FromValue for BTreeMap<String, T> {
+    fn from_value(value: Value) -> Result<Self, FromValueError> {
+        from_object(value)
+    }
+}
+
+impl<T: IntoValue, S: BuildHasher> IntoValue for HashMap<String, T, S> {
+    fn into_value(self) -> Value {
+        Value::Object(
+            self.into_iter()
+                .map(|(key, value)| (key, value.into_value()))
+                .collect(),
+        )
+    }
+}
+
+impl<T: FromValue, S: BuildHasher + Default> FromValue for HashMap<String, T, S> {
+    fn from_value(value: Value) -> Result<Self, FromValueError> {
+        from_object(value)
+    }
+}
+
+impl<T: IntoValue, S: BuildHasher> IntoValue for IndexMap<String, T, S> {
+    fn into_value(self) -> Value {
+        Value::Object(
+            self.into_iter()
+                .map(|(key, value)| (key, value.into_value()))
+                .collect(),
+        )
+    }
+}
+
+impl<T: FromValue, S: BuildHasher + Default> FromValue for IndexMap<String, T, S> {
+    fn from_value(value: Value) -> Result<Self, FromValueError> {
+        from_object(value)
+    }
+}
+
+fn from_object<T, M>(value: Value) -> Result<M, FromValueError>
+where
+    T: FromValue,
+    M: FromIterator<(String, T)>,
+{
+    match value {
+        Value::Object(object) => object
+            .into_iter()
+            .map(|(key, value)| match T::from_value(value) {
+                Ok(value) => Ok((key, value)),
+                Err(error) => Err(error.at_key(&key)),
+            })
+            .collect(),
+        other => Err(FromValueError::expected("object", &other)),
+    }
+}
+
+// Tuples are lists of exactly their length, as resource arguments are.
+macro_rules! impl_value_for_tuple {
+    ($len:expr => $($name:ident $index:tt),+) => {
+        impl<$($name: IntoValue),+> IntoValue for ($($name,)+) {
+            fn into_value(self) -> Value {
+                Value::List(vec![$(self.$index.into_value()),+])
+            }
+        }
+
+        impl<$($name: FromValue),+> FromValue for ($($name,)+) {
+            fn from_value(value: Value) -> Result<Self, FromValueError> {
+                match value {
+                    Value::List(list) if list.len() == $len => {
+                        let mut list = list.into_iter();
+                        Ok(($(from_element::<$name>(list.next().unwrap(), $index)?,)+))
+                    }
+                    other => Err(FromValueError::expected(
+                        concat!("a list of ", stringify!($len)),
+                        &other,
+                    )),
+                }
+            }
+        }
+    };
+}
+
+impl_value_for_tuple!(1 => A 0);
+impl_value_for_tuple!(2 => A 0, B 1);
+impl_value_for_tuple!(3 => A 0, B 1, C 2);
+impl_value_for_tuple!(4 => A 0, B 1, C 2, D 3);
+
+/// Converts any type that implements serde's traits, through its JSON form.
+/// This is the plain JSON `Value`s serialize to, not the tagged form of
+/// `json`: date-times, durations, big numbers and bytes are strings, and
+/// non-finite floats `null`.
+///
+/// ```
+/// use askql_parser::{FromValue, IntoValue, Serde, Value};
+/// use std::net::Ipv4Addr;
+///
+/// let value = Serde(Ipv4Addr::LOCALHOST).into_value();
+/// assert_eq!(value, Value::String("127.0.0.1".to_string()));
+/// assert_eq!(Serde::<Ipv4Addr>::from_value(value).unwrap().0, Ipv4Addr::LOCALHOST);
+/// ```
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq, Hash)]
+pub struct Serde<T>(pub T);
+
+/// # Panics
+///
+/// When `T`'s `Serialize` impl fails, as it does for maps whose keys are not
+/// strings; `try_into_value` returns the error instead.
+impl<T: Serialize> IntoValue for Serde<T> {
+    fn into_value(self) -> Value {
+        self.try_into_value()
+            .expect("value cannot be represented as JSON")
+    }
+}
+
+impl<T: Serialize> TryIntoValue for Serde<T> {
+    type Error = serde_json::Error;
+
+    fn try_into_value(self) -> Result<Value, serde_json::Error> {
+        serde_json::to_value(&self.0).and_then(serde_json::from_value)
+    }
+}
+
+impl<T: DeserializeOwned> FromValue for Serde<T> {
+    fn from_value(value: Value) -> Result<Self, FromValueError> {
+        serde_json::to_value(&value)
+            .and_then(serde_json::from_value)
+            .map(Serde)
+            .map_err(|error| FromValueError::new(error.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::value::Number;
+
+    #[test]
+    fn test_numbers() {
+        assert_eq!(i8::from_value(Value::Float(-128.0)), Ok(-128));
+        assert_eq!(
+            u64::from_value(Value::Number(Number("1e3".to_string()))),
+            Ok(1000)
+        );
+        assert_eq!(
+            u128::MAX.into_value(),
+            Value::BigInt("340282366920938463463374607431768211455".parse().unwrap())
+        );
+        assert_eq!(
+            u8::from_value(Value::Int(256)).unwrap_err().to_string(),
+            "expected u8, found int"
+        );
+        assert_eq!(
+            i64::from_value(Value::Float(0.5)).unwrap_err().to_string(),
+            "expected i64, found float"
+        );
+        assert_eq!(f64::from_value(Value::Int(3)), Ok(3.0));
+        assert_eq!(f32::from_value(Value::Float(0.5)), Ok(0.5));
+        assert_eq!(
+            f32::from_value(Value::Float(f64::NEG_INFINITY)),
+            Ok(f32::NEG_INFINITY)
+        );
+        assert_eq!(
+            f32::from_value(Value::Float(1e39)).unwrap_err().to_string(),
+            "expected f32, found float"
+        );
+        assert_eq!(
+            f32::from_value(Value::Number(Number("-1e300".to_string())))
+                .unwrap_err()
+                .to_string(),
+            "expected f32, found number"
+        );
+        let big = |text: &str| text.parse::<BigInt>().unwrap();
+        assert_eq!(BigInt::from_value(Value::Float(2.0)), Ok(big("2")));
+        assert_eq!(
+            BigInt::from_value(Value::Float(1e20)),
+            Ok(big("100000000000000000000"))
+        );
+        assert_eq!(
+            BigInt::from_value(Value::Decimal("10.00".parse().unwrap())),
+            Ok(big("10"))
+        );
+        assert_eq!(
+            BigInt::from_value(Value::Number(Number("1e20".to_string()))),
+            Ok(big("100000000000000000000"))
+        );
+        assert_eq!(
+            BigInt::from_value(Value::Decimal("0.5".parse().unwrap()))
+                .unwrap_err()
+                .to_string(),
+            "expected bigint, found decimal"
+        );
+        assert_eq!(
+            BigDecimal::from_value(Value::Float(0.1)),
+            Ok("0.1".parse().unwrap())
+        );
+    }
+
+    #[test]
+    fn test_temporal() {
+        let date_time = parse_date_time("2020-05-01T10:30:00Z").unwrap();
+        assert_eq!(
+            DateTime::<Utc>::from_value(Value::String("2020-05-01T12:30:00+02:00".to_string())),
+            Ok(date_time)
+        );
+        assert_eq!(
+            TimeDelta::from_value(TimeDelta::minutes(90).into_value()),
+            Ok(TimeDelta::minutes(90))
+        );
+        assert_eq!(
+            TimeDelta::from_value(Value::String("soon".to_string()))
+                .unwrap_err()
+                .message(),
+            "expected duration, found string"
+        );
+    }
+
+    #[test]
+    fn test_bytes() {
+        assert_eq!(Bytes(vec![0, 255]).into_value(), Value::Bytes(vec![0, 255]));
+        assert_eq!(
+            vec![0u8, 255].into_value(),
+            Value::List(vec![Value::Int(0), Value::Int(255)])
+        );
+        assert_eq!(
+            Bytes::from_value(Value::Bytes(vec![1, 2])),
+            Ok(Bytes(vec![1, 2]))
+        );
+        assert_eq!(
+            Bytes::from_value(Value::String("AP8=".to_string())),
+            Ok(Bytes(vec![0, 255]))
+        );
+        assert_eq!(
+            Bytes::from_value(Value::String("not base64".to_string()))
+                .unwrap_err()
+                .to_string(),
+            "expected bytes, found string"
+        );
+        assert_eq!(
+            Option::<Bytes>::from_value(Value::List(vec![]))
+                .unwrap_err()
+                .to_string(),
+            "expected bytes, found list"
+        );
+    }
+
+    #[test]
+    fn test_serde_uses_plain_json() {
+        let value = Value::List(vec![
+            Value::Bytes(b"hi".to_vec()),
+            Value::Duration(TimeDelta::hours(2)),
+            Value::Float(f64::NAN),
+        ]);
+        assert_eq!(
+            Serde::<Vec<Option<String>>>::from_value(value).map(|serde| serde.0),
+            Ok(vec![
+                Some("aGk=".to_string()),
+                Some("PT2H".to_string()),
+                None
+            ])
+        );
+        let mut map = BTreeMap::new();
+        map.insert("$bytes", "!");
+        let mut object = Object::new();
+        object.insert("$bytes".to_string(), Value::String("!".to_string()));
+        assert_eq!(Serde(map).into_value(), Value::Object(object));
+        let mut map = BTreeMap::new();
+        map.insert(vec![1], "one");
+        assert_eq!(
+            Serde(map).try_into_value().unwrap_err().to_string(),
+            "key must be a string"
+        );
+    }
+
+    #[test]
+    fn test_collections() {
+        let mut map = HashMap::new();
+        map.insert("a".to_string(), vec![(1, "x")]);
+        let value = map.clone().into_value();
+        assert_eq!(
+            HashMap::<String, Vec<(i32, String)>>::from_value(value),
+            Ok(vec![("a".to_string(), vec![(1, "x".to_string())])]
+                .into_iter()
+                .collect())
+        );
+        let error = Vec::<BTreeMap<String, i32>>::from_value(Value::List(vec![
+            Value::Object(Object::new()),
+            Value::Object(vec![("id".to_string(), Value::Null)].into_iter().collect()),
+        ]))
+        .unwrap_err();
+        assert_eq!(error.to_string(), "expected i32, found null at `[1].id`");
+        assert_eq!(
+            <(bool, String)>::from_value(Value::List(vec![Value::Boolean(true)]))
+                .unwrap_err()
+                .message(),
+            "expected a list of 2, found list"
+        );
+    }
+
+    #[test]
+    fn test_take_field() {
+        let mut object: Object = vec![("id".to_string(), Value::Int(1))]
+            .into_iter()
+            .collect();
+        assert_eq!(take_field::<i64>(&mut object, "id"), Ok(1));
+        assert_eq!(take_field::<Option<i64>>(&mut object, "id"), Ok(None));
+        assert_eq!(
+            take_field::<i64>(&mut object, "id")
+                .unwrap_err()
+                .to_string(),
+            "missing field `id`"
+        );
+    }
+}
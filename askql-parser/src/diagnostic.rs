@@ -0,0 +1,121 @@
+use crate::span::Span;
+use std::fmt;
+
+/// A syntax error located in the source, with what the parser expected there
+/// and what it found instead.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Diagnostic {
+    pub message: String,
+    pub span: Span,
+    pub expected: Vec<String>,
+    pub found: Option<String>,
+}
+
+impl Diagnostic {
+    pub fn new(message: String, span: Span) -> Self {
+        Self {
+            message,
+            span,
+            expected: Vec::new(),
+            found: None,
+        }
+    }
+
+    pub fn expecting(expected: Vec<String>, found: Option<String>, span: Span) -> Self {
+        let message = format!(
+            "expected {}, found {}",
+            one_of(&expected),
+            found.as_deref().unwrap_or("end of input")
+        );
+        Self {
+            message,
+            span,
+            expected,
+            found,
+        }
+    }
+
+    pub fn line(&self) -> usize {
+        self.span.start.line
+    }
+
+    pub fn column(&self) -> usize {
+        self.span.start.column
+    }
+
+    /// Renders the message followed by the offending source line with the
+    /// span underlined:
+    ///
+    /// ```text
+    /// error: expected `,` or `)`, found `;`
+    ///  --> 1:11
+    ///   |
+    /// 1 | ask(call(1;
+    ///   |           ^
+    /// ```
+    pub fn render(&self, code: &str) -> String {
+        let line_number = self.line().to_string();
+        let gutter = " ".repeat(line_number.len());
+        let line = code.lines().nth(self.line() - 1).unwrap_or("");
+        let mut marker = String::new();
+        for char in line.chars().take(self.column() - 1) {
+            marker.push(if char == '\t' { '\t' } else { ' ' });
+        }
+        let width = if self.span.end.line == self.span.start.line {
+            self.span.end.column.saturating_sub(self.span.start.column)
+        } else {
+            line.chars().count().saturating_sub(self.column() - 1)
+        };
+        marker.push_str(&"^".repeat(width.max(1)));
+        format!(
+            "error: {}\n{}--> {}:{}\n{} |\n{} | {}\n{} | {}",
+            self.message,
+            gutter,
+            self.line(),
+            self.column(),
+            gutter,
+            line_number,
+            line,
+            gutter,
+            marker
+        )
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}: {}", self.line(), self.column(), self.message)
+    }
+}
+
+fn one_of(expected: &[String]) -> String {
+    match expected.split_last() {
+        None => "nothing".to_string(),
+        Some((last, [])) => last.clone(),
+        Some((last, rest)) => format!("{} or {}", rest.join(", "), last),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::span::SourceMap;
+
+    #[test]
+    fn test_render_code_frame() {
+        let code = "ask(\n\tcall(1;\n)";
+        let diagnostic = Diagnostic::expecting(
+            vec!["`,`".to_string(), "`)`".to_string()],
+            Some("`;`".to_string()),
+            SourceMap::new(code).span(12, 13),
+        );
+        assert_eq!(
+            diagnostic.to_string(),
+            "2:8: expected `,` or `)`, found `;`"
+        );
+        assert_eq!(
+            diagnostic.render(code),
+            "error: expected `,` or `)`, found `;`\n --> 2:8\n  |\n2 | \tcall(1;\n  | \t      ^"
+        );
+    }
+}
@@ -0,0 +1,122 @@
+/// Quotes `string` as an AskCode string literal, escaping backslashes,
+/// `quote` and control characters.
+pub fn escape(string: &str, quote: char) -> String {
+    let mut escaped = String::with_capacity(string.len() + 2);
+    escaped.push(quote);
+    for char in string.chars() {
+        match char {
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\t' => escaped.push_str("\\t"),
+            '\r' => escaped.push_str("\\r"),
+            '\0' => escaped.push_str("\\0"),
+            char if char == quote => {
+                escaped.push('\\');
+                escaped.push(char);
+            }
+            char if char.is_control() => {
+                escaped.push_str(&format!("\\u{{{:x}}}", char as u32));
+            }
+            char => escaped.push(char),
+        }
+    }
+    escaped.push(quote);
+    escaped
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub(crate) struct EscapeError {
+    pub message: String,
+    pub end: usize,
+}
+
+/// Decodes the escape sequence whose backslash is at byte `index` of `code`,
+/// returning the character and the index right after the sequence.
+pub(crate) fn unescape(code: &str, index: usize) -> Result<(char, usize), EscapeError> {
+    let char = match code[index + 1..].chars().next() {
+        Some(char) => char,
+        None => {
+            return Err(EscapeError {
+                message: "unterminated escape sequence".to_string(),
+                end: index + 1,
+            })
+        }
+    };
+    let decoded = match char {
+        'n' => '\n',
+        't' => '\t',
+        'r' => '\r',
+        '0' => '\0',
+        '\\' | '\'' | '"' => char,
+        'u' => return unescape_unicode(code.as_bytes(), index),
+        char => {
+            return Err(EscapeError {
+                message: format!("unknown escape sequence `\\{}`", char),
+                end: index + 1 + char.len_utf8(),
+            })
+        }
+    };
+    Ok((decoded, index + 2))
+}
+
+// `\u{...}` with one to six hex digits naming a Unicode scalar value.
+fn unescape_unicode(code: &[u8], index: usize) -> Result<(char, usize), EscapeError> {
+    let invalid = |end: usize| EscapeError {
+        message: "invalid unicode escape, expected `\\u{...}` with 1 to 6 hex digits".to_string(),
+        end,
+    };
+    let mut end = index + 2;
+    if code.get(end) != Some(&b'{') {
+        return Err(invalid(end));
+    }
+    end += 1;
+    let digits_start = end;
+    while end < code.len() && code[end].is_ascii_hexdigit() {
+        end += 1;
+    }
+    if code.get(end) != Some(&b'}') || end == digits_start || end - digits_start > 6 {
+        return Err(invalid(end));
+    }
+    // Hex digits are ASCII, so this cannot fail.
+    let digits = std::str::from_utf8(&code[digits_start..end]).unwrap();
+    match u32::from_str_radix(digits, 16)
+        .ok()
+        .and_then(std::char::from_u32)
+    {
+        Some(char) => Ok((char, end + 1)),
+        None => Err(EscapeError {
+            message: format!("`{}` is not a valid unicode character", digits),
+            end: end + 1,
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn unescape_str(code: &str) -> Result<(char, usize), EscapeError> {
+        unescape(code, 0)
+    }
+
+    #[test]
+    fn test_escape() {
+        assert_eq!(escape("it's", '\''), r"'it\'s'");
+        assert_eq!(escape("say \"hi\"", '"'), r#""say \"hi\"""#);
+        assert_eq!(escape("a\\b\n\tc\u{7}", '\''), r"'a\\b\n\tc\u{7}'");
+        assert_eq!(escape("çé 😀", '\''), "'çé 😀'");
+    }
+
+    #[test]
+    fn test_unescape() {
+        assert_eq!(unescape_str(r"\n"), Ok(('\n', 2)));
+        assert_eq!(unescape_str(r"\'"), Ok(('\'', 2)));
+        assert_eq!(unescape_str(r"\u{1F600}"), Ok(('😀', 9)));
+        assert_eq!(unescape_str("\\é").unwrap_err().end, 3);
+        assert_eq!(unescape_str(r"\q").unwrap_err().end, 2);
+        assert!(unescape_str(r"\u{110000}").is_err());
+        assert!(unescape_str(r"\u{}").is_err());
+        assert!(unescape_str(r"\u41").is_err());
+        assert!(unescape_str("\\").is_err());
+    }
+}
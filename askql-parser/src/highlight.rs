@@ -0,0 +1,157 @@
+//! Syntax highlighting for AskCode, built on `tokenize`.
+//!
+//! Both highlighters accept any input, including code that does not parse:
+//! what the parser would reject is marked as invalid rather than dropped.
+
+use crate::token::{tokenize, Token, TokenKind};
+
+/// Colours `code` with ANSI escape codes for a terminal.
+pub fn highlight_ansi(code: &str) -> String {
+    let mut out = String::with_capacity(code.len() * 2);
+    for (token, class) in classify(code) {
+        match class.and_then(Class::ansi) {
+            Some(colour) => {
+                out.push_str(colour);
+                out.push_str(token.text);
+                out.push_str("\x1b[0m");
+            }
+            None => out.push_str(token.text),
+        }
+    }
+    out
+}
+
+/// Renders `code` as HTML, wrapping tokens in `<span class="askql-...">`
+/// with the classes `askql-call`, `askql-identifier`, `askql-keyword`,
+/// `askql-number`, `askql-string`, `askql-comment`, `askql-punctuation` and
+/// `askql-invalid`.
+/// Whitespace is kept as is, so the result belongs in a `<pre>`.
+pub fn highlight_html(code: &str) -> String {
+    let mut out = String::with_capacity(code.len() * 2);
+    for (token, class) in classify(code) {
+        match class {
+            Some(class) => {
+                out.push_str("<span class=\"askql-");
+                out.push_str(class.name());
+                out.push_str("\">");
+                escape_html(token.text, &mut out);
+                out.push_str("</span>");
+            }
+            None => escape_html(token.text, &mut out),
+        }
+    }
+    out
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+enum Class {
+    // An identifier followed by `(`.
+    Call,
+    Identifier,
+    Keyword,
+    Number,
+    String,
+    Comment,
+    Punctuation,
+    Invalid,
+}
+
+impl Class {
+    fn name(self) -> &'static str {
+        match self {
+            Class::Call => "call",
+            Class::Identifier => "identifier",
+            Class::Keyword => "keyword",
+            Class::Number => "number",
+            Class::String => "string",
+            Class::Comment => "comment",
+            Class::Punctuation => "punctuation",
+            Class::Invalid => "invalid",
+        }
+    }
+
+    // Punctuation keeps the terminal's default colour.
+    fn ansi(self) -> Option<&'static str> {
+        match self {
+            Class::Call => Some("\x1b[34m"),
+            Class::Identifier => Some("\x1b[36m"),
+            Class::Keyword => Some("\x1b[35m"),
+            Class::Number => Some("\x1b[33m"),
+            Class::String => Some("\x1b[32m"),
+            Class::Comment => Some("\x1b[90m"),
+            Class::Punctuation => None,
+            Class::Invalid => Some("\x1b[4;31m"),
+        }
+    }
+}
+
+// Pairs every token with its class; whitespace has none.
+fn classify(code: &str) -> Vec<(Token<'_>, Option<Class>)> {
+    let tokens: Vec<Token> = tokenize(code).collect();
+    tokens
+        .iter()
+        .enumerate()
+        .map(|(index, token)| {
+            let class = match token.kind {
+                TokenKind::Whitespace => None,
+                TokenKind::Comment(_) => Some(Class::Comment),
+                TokenKind::Identifier => {
+                    let called = tokens[index + 1..]
+                        .iter()
+                        .find(|next| {
+                            !matches!(next.kind, TokenKind::Whitespace | TokenKind::Comment(_))
+                        })
+                        .is_some_and(|next| next.text == "(");
+                    Some(if called {
+                        Class::Call
+                    } else {
+                        Class::Identifier
+                    })
+                }
+                TokenKind::Keyword => Some(Class::Keyword),
+                TokenKind::Number => Some(Class::Number),
+                TokenKind::String => Some(Class::String),
+                TokenKind::Punctuation => Some(Class::Punctuation),
+                TokenKind::Invalid => Some(Class::Invalid),
+            };
+            (*token, class)
+        })
+        .collect()
+}
+
+fn escape_html(text: &str, out: &mut String) {
+    for char in text.chars() {
+        match char {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&#39;"),
+            char => out.push(char),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_highlight_html() {
+        assert_eq!(
+            highlight_html("f(x, '<a>') // c"),
+            "<span class=\"askql-call\">f</span><span class=\"askql-punctuation\">(</span>\
+             <span class=\"askql-identifier\">x</span><span class=\"askql-punctuation\">,</span> \
+             <span class=\"askql-string\">&#39;&lt;a&gt;&#39;</span>\
+             <span class=\"askql-punctuation\">)</span> <span class=\"askql-comment\">// c</span>"
+        );
+    }
+
+    #[test]
+    fn test_highlight_ansi() {
+        assert_eq!(
+            highlight_ansi("f (1, ;)"),
+            "\x1b[34mf\x1b[0m (\x1b[33m1\x1b[0m, \x1b[4;31m;\x1b[0m)"
+        );
+    }
+}
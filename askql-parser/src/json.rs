@@ -0,0 +1,478 @@
+//! JSON interchange format for AskCode trees, for shipping pre-parsed
+//! programs between services and storing them.
+//!
+//! A node is `{"AskCode": {"name": ..., "params": [...]}}`, with `params`
+//! `null` for a bare identifier, and a value is `{"Value": ...}` holding the
+//! value as JSON. `ask(call(get('+'), x, 'a'))` is:
+//!
+//! ```json
+//! {"AskCode": {"name": "ask", "params": [
+//!   {"AskCode": {"name": "call", "params": [
+//!     {"AskCode": {"name": "get", "params": [{"Value": "+"}]}},
+//!     {"AskCode": {"name": "x", "params": null}},
+//!     {"Value": "a"}
+//!   ]}}
+//! ]}}
+//! ```
+//!
+//! Values map to JSON the obvious way: `Null` to `null`, `List` to arrays,
+//! `Object` to objects and so on. `Int`s are written as integers and `Float`s
+//! always with a fraction or exponent, so both come back as they were; JSON
+//! integers out of the `Int` range are read as `BigInt`s. A `Number` literal
+//! is written as the value the VM would evaluate it to.
+//!
+//! Values JSON has no type for are written as an object with a single key
+//! naming the variant, holding its text, so that they come back as they were:
+//!
+//! - `BigInt`: `{"$bigint": "9223372036854775808"}`
+//! - `Decimal`: `{"$decimal": "0.10000000000000000001"}`
+//! - NaN and infinite `Float`s: `{"$float": "NaN"}`, with `"Infinity"` and
+//!   `"-Infinity"`
+//! - a `Number` literal with no value: `{"$number": "1x"}`
+//! - `DateTime`: `{"$datetime": "2020-05-01T10:30:00Z"}`
+//! - `Duration`: `{"$duration": "P1DT2H"}`
+//! - `Bytes`, in base64: `{"$bytes": "aGk="}`
+//!
+//! Dates and durations are in ISO-8601 (see `temporal`). An `Object` whose
+//! first key is one of these is itself wrapped, as `{"$object": {...}}`.
+//!
+//! The tags are only used here. Serializing a `Value` on its own, as query
+//! results are, writes these as plain strings instead.
+
+use crate::askcode::AskCodeOrValue;
+use crate::temporal::{format_date_time, format_duration, parse_date_time, parse_duration};
+use crate::value::{Number, Object, Value, ValueVisitor};
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use serde::de::{self, Deserialize, Deserializer, MapAccess, SeqAccess, Visitor};
+use serde::ser::{Serialize, SerializeMap, Serializer};
+use std::fmt;
+
+pub fn to_json(code: &AskCodeOrValue) -> String {
+    serde_json::to_string(code).unwrap()
+}
+
+pub fn from_json(json: &str) -> Result<AskCodeOrValue, serde_json::Error> {
+    serde_json::from_str(json)
+}
+
+const BIG_INT_TAG: &str = "$bigint";
+const DECIMAL_TAG: &str = "$decimal";
+const FLOAT_TAG: &str = "$float";
+const NUMBER_TAG: &str = "$number";
+const DATE_TIME_TAG: &str = "$datetime";
+const DURATION_TAG: &str = "$duration";
+const BYTES_TAG: &str = "$bytes";
+const OBJECT_TAG: &str = "$object";
+const TAGS: &[&str] = &[
+    BIG_INT_TAG,
+    DECIMAL_TAG,
+    FLOAT_TAG,
+    NUMBER_TAG,
+    DATE_TIME_TAG,
+    DURATION_TAG,
+    BYTES_TAG,
+    OBJECT_TAG,
+];
+
+pub(crate) fn serialize_value<S: Serializer>(
+    value: &Value,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    Tagged(value).serialize(serializer)
+}
+
+pub(crate) fn deserialize_value<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Value, D::Error> {
+    TaggedValue::deserialize(deserializer).map(|tagged| tagged.0)
+}
+
+fn serialize_tagged<S, T>(serializer: S, tag: &str, value: &T) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+    T: Serialize + ?Sized,
+{
+    let mut map = serializer.serialize_map(Some(1))?;
+    map.serialize_entry(tag, value)?;
+    map.end()
+}
+
+// A value written in the tagged form.
+struct Tagged<'a>(&'a Value);
+
+impl Serialize for Tagged<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self.0 {
+            Value::Number(number) => match number.to_value() {
+                Ok(value) => Tagged(&value).serialize(serializer),
+                Err(_) => serialize_tagged(serializer, NUMBER_TAG, &number.0),
+            },
+            Value::Float(float) if float.is_nan() => serialize_tagged(serializer, FLOAT_TAG, "NaN"),
+            Value::Float(float) if float.is_infinite() => {
+                let text = if *float > 0.0 {
+                    "Infinity"
+                } else {
+                    "-Infinity"
+                };
+                serialize_tagged(serializer, FLOAT_TAG, text)
+            }
+            Value::BigInt(int) => serialize_tagged(serializer, BIG_INT_TAG, &int.to_string()),
+            Value::Decimal(decimal) => {
+                serialize_tagged(serializer, DECIMAL_TAG, &decimal.to_string())
+            }
+            Value::DateTime(date_time) => {
+                serialize_tagged(serializer, DATE_TIME_TAG, &format_date_time(date_time))
+            }
+            Value::Duration(duration) => {
+                serialize_tagged(serializer, DURATION_TAG, &format_duration(duration))
+            }
+            Value::Bytes(bytes) => serialize_tagged(serializer, BYTES_TAG, &BASE64.encode(bytes)),
+            Value::List(list) => serializer.collect_seq(list.iter().map(Tagged)),
+            Value::Object(object) => match object.keys().next() {
+                Some(key) if TAGS.contains(&key.as_str()) => {
+                    serialize_tagged(serializer, OBJECT_TAG, &Entries(object))
+                }
+                _ => Entries(object).serialize(serializer),
+            },
+            // Null, booleans, `Int`s, finite `Float`s and strings.
+            value => value.serialize(serializer),
+        }
+    }
+}
+
+// An object's entries, without the tag an object starting with a tag's key
+// gets.
+struct Entries<'a>(&'a Object);
+
+impl Serialize for Entries<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_map(self.0.iter().map(|(key, value)| (key, Tagged(value))))
+    }
+}
+
+// A value read from the tagged form.
+struct TaggedValue(Value);
+
+impl<'de> Deserialize<'de> for TaggedValue {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(TaggedVisitor).map(TaggedValue)
+    }
+}
+
+struct TaggedVisitor;
+
+impl<'de> Visitor<'de> for TaggedVisitor {
+    type Value = Value;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("any valid JSON value")
+    }
+
+    fn visit_bool<E: de::Error>(self, value: bool) -> Result<Value, E> {
+        ValueVisitor.visit_bool(value)
+    }
+
+    fn visit_i64<E: de::Error>(self, value: i64) -> Result<Value, E> {
+        ValueVisitor.visit_i64(value)
+    }
+
+    fn visit_u64<E: de::Error>(self, value: u64) -> Result<Value, E> {
+        ValueVisitor.visit_u64(value)
+    }
+
+    fn visit_f64<E: de::Error>(self, value: f64) -> Result<Value, E> {
+        ValueVisitor.visit_f64(value)
+    }
+
+    fn visit_str<E: de::Error>(self, value: &str) -> Result<Value, E> {
+        ValueVisitor.visit_str(value)
+    }
+
+    fn visit_string<E: de::Error>(self, value: String) -> Result<Value, E> {
+        ValueVisitor.visit_string(value)
+    }
+
+    fn visit_none<E: de::Error>(self) -> Result<Value, E> {
+        ValueVisitor.visit_none()
+    }
+
+    fn visit_some<D: Deserializer<'de>>(self, deserializer: D) -> Result<Value, D::Error> {
+        deserialize_value(deserializer)
+    }
+
+    fn visit_unit<E: de::Error>(self) -> Result<Value, E> {
+        ValueVisitor.visit_unit()
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Value, A::Error> {
+        let mut list = Vec::new();
+        while let Some(TaggedValue(value)) = seq.next_element()? {
+            list.push(value);
+        }
+        Ok(Value::List(list))
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Value, A::Error> {
+        let key: String = match map.next_key()? {
+            Some(key) => key,
+            None => return Ok(Value::Object(Object::new())),
+        };
+        if !TAGS.contains(&key.as_str()) {
+            let mut object = Object::new();
+            object.insert(key, map.next_value::<TaggedValue>()?.0);
+            while let Some((key, TaggedValue(value))) = map.next_entry()? {
+                object.insert(key, value);
+            }
+            return Ok(Value::Object(object));
+        }
+        let value = if key == OBJECT_TAG {
+            Value::Object(map.next_value::<EntriesOf>()?.0)
+        } else {
+            let text: String = map.next_value()?;
+            tagged(&key, &text).ok_or_else(|| {
+                de::Error::invalid_value(de::Unexpected::Str(&text), &&*format!("a {}", key))
+            })?
+        };
+        if map.next_key::<String>()?.is_some() {
+            return Err(de::Error::custom(format!("`{}` must be the only key", key)));
+        }
+        Ok(value)
+    }
+}
+
+// The value a tag's text stands for, or `None` when it does not parse.
+fn tagged(tag: &str, text: &str) -> Option<Value> {
+    Some(match tag {
+        BIG_INT_TAG => Value::BigInt(text.parse().ok()?),
+        // Held to the range of literals, as a decimal with a huge exponent
+        // makes later arithmetic on it run away.
+        DECIMAL_TAG if Number(text.to_string()).to_value().is_ok() => {
+            Value::Decimal(text.parse().ok()?)
+        }
+        FLOAT_TAG => Value::Float(match text {
+            "NaN" => f64::NAN,
+            "Infinity" => f64::INFINITY,
+            "-Infinity" => f64::NEG_INFINITY,
+            _ => return None,
+        }),
+        NUMBER_TAG => Value::Number(Number(text.to_string())),
+        DATE_TIME_TAG => Value::DateTime(parse_date_time(text)?),
+        DURATION_TAG => Value::Duration(parse_duration(text)?),
+        BYTES_TAG => Value::Bytes(BASE64.decode(text).ok()?),
+        _ => return None,
+    })
+}
+
+// An object's entries, read as they are even if the first key is a tag's.
+struct EntriesOf(Object);
+
+impl<'de> Deserialize<'de> for EntriesOf {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct EntriesVisitor;
+
+        impl<'de> Visitor<'de> for EntriesVisitor {
+            type Value = EntriesOf;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str("an object")
+            }
+
+            fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<EntriesOf, A::Error> {
+                let mut object = Object::new();
+                while let Some((key, TaggedValue(value))) = map.next_entry()? {
+                    object.insert(key, value);
+                }
+                Ok(EntriesOf(object))
+            }
+        }
+
+        deserializer.deserialize_map(EntriesVisitor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::askcode::AskCode;
+    use crate::parse::parse;
+    use crate::temporal::{DateTime, TimeDelta, Utc};
+    use crate::value::{BigDecimal, Number, Object, Value};
+    use proptest::prelude::*;
+
+    #[test]
+    fn test_json_format() {
+        let code = parse("ask(call(get('+'),x,'a'))".to_string(), false).unwrap();
+        let json = to_json(&code);
+        assert_eq!(
+            json,
+            r#"{"AskCode":{"name":"ask","params":[{"AskCode":{"name":"call","params":[{"AskCode":{"name":"get","params":[{"Value":"+"}]}},{"AskCode":{"name":"x","params":null}},{"Value":"a"}]}}]}}"#
+        );
+        assert_eq!(from_json(&json).unwrap(), code);
+    }
+
+    #[test]
+    fn test_number_literals() {
+        let code = parse("max(2, 2.5, 1e3)".to_string(), false).unwrap();
+        let expected = AskCodeOrValue::new_ask_code(AskCode::new(
+            "max".to_string(),
+            Some(vec![
+                AskCodeOrValue::new_value(Value::Int(2)),
+                AskCodeOrValue::new_value(Value::Float(2.5)),
+                AskCodeOrValue::new_value(Value::Float(1000.0)),
+            ]),
+        ));
+        assert_eq!(from_json(&to_json(&code)).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_wide_numbers() {
+        let value: Value =
+            serde_json::from_str("[9223372036854775807, 18446744073709551615, 0.1]").unwrap();
+        assert_eq!(
+            format!("{:?}", value),
+            format!(
+                "{:?}",
+                Value::List(vec![
+                    Value::Int(i64::MAX),
+                    Value::BigInt(u64::MAX.into()),
+                    Value::Float(0.1),
+                ])
+            )
+        );
+        let code = AskCodeOrValue::Value(value);
+        assert_eq!(
+            to_json(&code),
+            r#"{"Value":[9223372036854775807,{"$bigint":"18446744073709551615"},0.1]}"#
+        );
+        assert_eq!(
+            format!("{:?}", from_json(&to_json(&code)).unwrap()),
+            format!("{:?}", code)
+        );
+    }
+
+    #[test]
+    fn test_tagged_values() {
+        let round_trip = |value: Value, json: &str| {
+            let code = AskCodeOrValue::Value(value);
+            assert_eq!(to_json(&code), format!(r#"{{"Value":{}}}"#, json));
+            let read = from_json(&to_json(&code)).unwrap();
+            assert_eq!(format!("{:?}", read), format!("{:?}", code));
+        };
+        round_trip(
+            Value::BigInt("-9223372036854775809".parse().unwrap()),
+            r#"{"$bigint":"-9223372036854775809"}"#,
+        );
+        round_trip(
+            Value::Decimal("0.10000000000000000001".parse().unwrap()),
+            r#"{"$decimal":"0.10000000000000000001"}"#,
+        );
+        round_trip(Value::Float(f64::NAN), r#"{"$float":"NaN"}"#);
+        round_trip(Value::Float(f64::INFINITY), r#"{"$float":"Infinity"}"#);
+        round_trip(Value::Float(f64::NEG_INFINITY), r#"{"$float":"-Infinity"}"#);
+        round_trip(
+            Value::Number(Number("1x".to_string())),
+            r#"{"$number":"1x"}"#,
+        );
+        round_trip(
+            Value::DateTime(crate::parse_date_time("2020-05-01T10:30:00.5Z").unwrap()),
+            r#"{"$datetime":"2020-05-01T10:30:00.500Z"}"#,
+        );
+        round_trip(
+            Value::Duration(crate::parse_duration("-P1DT2H").unwrap()),
+            r#"{"$duration":"-P1DT2H"}"#,
+        );
+        round_trip(Value::Bytes(b"hi".to_vec()), r#"{"$bytes":"aGk="}"#);
+        let mut object = Object::new();
+        object.insert("$bytes".to_string(), Value::Int(1));
+        object.insert("a".to_string(), Value::Bytes(vec![]));
+        round_trip(
+            Value::Object(object),
+            r#"{"$object":{"$bytes":1,"a":{"$bytes":""}}}"#,
+        );
+        let mut object = Object::new();
+        object.insert("$ref".to_string(), Value::Null);
+        round_trip(Value::Object(object), r#"{"$ref":null}"#);
+        round_trip(
+            Value::List(vec![Value::Bytes(vec![0]), Value::Int(1)]),
+            r#"[{"$bytes":"AA=="},1]"#,
+        );
+    }
+
+    #[test]
+    fn test_invalid_tags() {
+        for json in &[
+            r#"{"$bigint":"1.5"}"#,
+            r#"{"$decimal":"1e99999999"}"#,
+            r#"{"$float":"inf"}"#,
+            r#"{"$datetime":"yesterday"}"#,
+            r#"{"$bytes":"!"}"#,
+            r#"{"$bytes":1}"#,
+            r#"{"$bytes":"","a":1}"#,
+            r#"{"$object":[]}"#,
+        ] {
+            let json = format!(r#"{{"Value":{}}}"#, json);
+            assert!(from_json(&json).is_err(), "{}", json);
+        }
+    }
+
+    #[test]
+    fn test_invalid_json() {
+        assert!(from_json(r#"{"AskCode":{"name":1}}"#).is_err());
+        assert!(from_json(r#"{"Node":{}}"#).is_err());
+    }
+
+    fn value() -> impl Strategy<Value = Value> {
+        let leaf = prop_oneof![
+            Just(Value::Null),
+            any::<bool>().prop_map(Value::Boolean),
+            any::<i64>().prop_map(Value::Int),
+            any::<f64>().prop_map(Value::Float),
+            any::<i128>().prop_map(|int| Value::BigInt(int.into())),
+            (any::<i64>(), -30..30i64)
+                .prop_map(|(digits, scale)| Value::Decimal(BigDecimal::new(digits.into(), scale))),
+            (
+                DateTime::<Utc>::MIN_UTC.timestamp()..=DateTime::<Utc>::MAX_UTC.timestamp(),
+                0..1_000_000_000u32
+            )
+                .prop_map(|(seconds, nanos)| Value::DateTime(
+                    DateTime::from_timestamp(seconds, nanos).unwrap()
+                )),
+            (-1_000_000_000_000..1_000_000_000_000i64)
+                .prop_map(|nanos| { Value::Duration(TimeDelta::nanoseconds(nanos)) }),
+            any::<String>().prop_map(Value::String),
+            any::<Vec<u8>>().prop_map(Value::Bytes),
+        ];
+        leaf.prop_recursive(3, 32, 5, |inner| {
+            prop_oneof![
+                prop::collection::vec(inner.clone(), 0..5).prop_map(Value::List),
+                prop::collection::vec((any::<String>(), inner), 0..5)
+                    .prop_map(|entries| Value::Object(entries.into_iter().collect())),
+            ]
+        })
+    }
+
+    // The variants in `value`, depth first, as equality compares numbers by
+    // value whatever their variant.
+    fn variants(value: &Value) -> Vec<std::mem::Discriminant<Value>> {
+        let mut found = vec![std::mem::discriminant(value)];
+        match value {
+            Value::List(list) => found.extend(list.iter().flat_map(variants)),
+            Value::Object(object) => found.extend(object.values().flat_map(variants)),
+            _ => {}
+        }
+        found
+    }
+
+    proptest! {
+        #[test]
+        fn prop_values_round_trip(value in value()) {
+            let read = match from_json(&to_json(&AskCodeOrValue::Value(value.clone()))) {
+                Ok(AskCodeOrValue::Value(read)) => read,
+                other => panic!("{:?}", other),
+            };
+            prop_assert_eq!(variants(&read), variants(&value));
+            prop_assert_eq!(read, value);
+        }
+    }
+}
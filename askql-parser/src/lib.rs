@@ -1,12 +1,37 @@
 #[macro_use]
 extern crate serde;
-#[macro_use]
-extern crate lazy_static;
 pub mod askcode;
 pub use askcode::*;
+pub mod askscript;
+pub use askscript::*;
+pub mod binary;
+pub use binary::*;
+pub mod convert;
+pub use convert::*;
+pub mod diagnostic;
+pub use diagnostic::*;
+pub mod escape;
+pub use escape::escape;
+pub mod highlight;
+pub use highlight::*;
+pub mod json;
+pub use json::*;
+mod number;
 pub mod parse;
 pub use parse::*;
+pub mod print;
+pub use print::*;
 pub mod reduce;
 pub use reduce::*;
+pub mod span;
+pub use span::*;
+pub mod temporal;
+pub use temporal::*;
+pub mod token;
+pub use token::*;
+pub mod trivia;
+pub use trivia::*;
 pub mod value;
 pub use value::*;
+pub mod visit;
+pub use visit::*;
@@ -0,0 +1,174 @@
+use num_bigint::BigInt;
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub(crate) struct NumberError {
+    pub message: String,
+    pub end: usize,
+}
+
+/// Scans the numeric literal starting at byte `index` of `code`, which must be
+/// a digit.
+///
+/// Literals are decimal with an optional fraction and exponent (`1.5e-3`) or
+/// `0x`, `0o` and `0b` integers, with `_` allowed between digits. Returns the
+/// literal as plain decimal text, without separators and with prefixed
+/// integers converted, and the index right after it.
+pub(crate) fn scan_number(code: &str, index: usize) -> Result<(String, usize), NumberError> {
+    let bytes = code.as_bytes();
+    let radix = match bytes.get(index + 1) {
+        Some(b'x') | Some(b'X') if bytes[index] == b'0' => Some(16),
+        Some(b'o') | Some(b'O') if bytes[index] == b'0' => Some(8),
+        Some(b'b') | Some(b'B') if bytes[index] == b'0' => Some(2),
+        _ => None,
+    };
+    let end = literal_end(bytes, index, radix.is_none());
+    let literal = &bytes[index..end];
+    let invalid = |reason: String| NumberError {
+        message: format!("invalid number literal `{}`: {}", &code[index..end], reason),
+        end,
+    };
+    match radix {
+        Some(radix) => {
+            let mut digits = String::new();
+            let pos = scan_digits(literal, 2, radix, &mut digits).map_err(invalid)?;
+            if let Some(byte) = literal.get(pos) {
+                return Err(invalid(format!("unexpected `{}`", *byte as char)));
+            }
+            // Any size, as decimal literals are.
+            let value = BigInt::parse_bytes(digits.as_bytes(), radix)
+                .expect("digits were checked against the radix");
+            Ok((value.to_string(), end))
+        }
+        None => {
+            let mut number = String::new();
+            let mut pos = scan_digits(literal, 0, 10, &mut number).map_err(invalid)?;
+            if literal.get(pos) == Some(&b'.') {
+                number.push('.');
+                pos = scan_digits(literal, pos + 1, 10, &mut number).map_err(invalid)?;
+            }
+            if let Some(b'e') | Some(b'E') = literal.get(pos) {
+                number.push('e');
+                pos += 1;
+                if let Some(sign @ b'+') | Some(sign @ b'-') = literal.get(pos) {
+                    number.push(*sign as char);
+                    pos += 1;
+                }
+                pos = scan_digits(literal, pos, 10, &mut number).map_err(invalid)?;
+            }
+            if let Some(byte) = literal.get(pos) {
+                return Err(invalid(format!("unexpected `{}`", *byte as char)));
+            }
+            Ok((number, end))
+        }
+    }
+}
+
+// Everything that could belong to the literal, so that a malformed one is
+// reported whole instead of being split into several tokens.
+fn literal_end(code: &[u8], mut index: usize, decimal: bool) -> usize {
+    while let Some(byte) = code.get(index) {
+        let exponent_sign =
+            decimal && (*byte == b'+' || *byte == b'-') && matches!(code[index - 1], b'e' | b'E');
+        if !(byte.is_ascii_alphanumeric() || *byte == b'_' || *byte == b'.' || exponent_sign) {
+            break;
+        }
+        index += 1;
+    }
+    index
+}
+
+// Pushes the digits at `literal[pos..]` onto `out` without their `_`
+// separators, returning the position after them.
+fn scan_digits(
+    literal: &[u8],
+    mut pos: usize,
+    radix: u32,
+    out: &mut String,
+) -> Result<usize, String> {
+    let start = pos;
+    while let Some(&byte) = literal.get(pos) {
+        if (byte as char).is_digit(radix) {
+            out.push(byte as char);
+        } else if byte == b'_' {
+            let between_digits = pos > start
+                && literal
+                    .get(pos + 1)
+                    .is_some_and(|&next| (next as char).is_digit(radix));
+            if !between_digits {
+                return Err("`_` must separate digits".to_string());
+            }
+        } else {
+            break;
+        }
+        pos += 1;
+    }
+    if pos == start {
+        return Err(match literal.get(pos) {
+            Some(byte) => format!("expected digits, found `{}`", *byte as char),
+            None => "expected digits".to_string(),
+        });
+    }
+    Ok(pos)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scan(code: &str) -> Result<(String, usize), String> {
+        scan_number(code, 0).map_err(|error| error.message)
+    }
+
+    #[test]
+    fn test_scan_number() {
+        assert_eq!(scan("42)"), Ok(("42".to_string(), 2)));
+        assert_eq!(scan("4.25,"), Ok(("4.25".to_string(), 4)));
+        assert_eq!(scan("1_000_000"), Ok(("1000000".to_string(), 9)));
+        assert_eq!(scan("1e6"), Ok(("1e6".to_string(), 3)));
+        assert_eq!(scan("2.5E-3"), Ok(("2.5e-3".to_string(), 6)));
+        assert_eq!(scan("0xff_ff"), Ok(("65535".to_string(), 7)));
+        assert_eq!(scan("0b1010"), Ok(("10".to_string(), 6)));
+        assert_eq!(scan("0o17"), Ok(("15".to_string(), 4)));
+        assert_eq!(
+            scan("0x1_0000_0000_0000_0000_0000_0000_0000_0000"),
+            Ok(("340282366920938463463374607431768211456".to_string(), 43))
+        );
+        assert_eq!(scan("1-2"), Ok(("1".to_string(), 1)));
+    }
+
+    #[test]
+    fn test_malformed_numbers() {
+        assert_eq!(
+            scan("1..2"),
+            Err("invalid number literal `1..2`: expected digits, found `.`".to_string())
+        );
+        assert_eq!(
+            scan("1.2.3"),
+            Err("invalid number literal `1.2.3`: unexpected `.`".to_string())
+        );
+        assert_eq!(
+            scan("1__0"),
+            Err("invalid number literal `1__0`: `_` must separate digits".to_string())
+        );
+        assert_eq!(
+            scan("10_"),
+            Err("invalid number literal `10_`: `_` must separate digits".to_string())
+        );
+        assert_eq!(
+            scan("1e"),
+            Err("invalid number literal `1e`: expected digits".to_string())
+        );
+        assert_eq!(
+            scan("0x"),
+            Err("invalid number literal `0x`: expected digits".to_string())
+        );
+        assert_eq!(
+            scan("0b102"),
+            Err("invalid number literal `0b102`: unexpected `2`".to_string())
+        );
+        assert_eq!(
+            scan("12abc"),
+            Err("invalid number literal `12abc`: unexpected `a`".to_string())
+        );
+    }
+}
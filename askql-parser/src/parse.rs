@@ -1,5 +1,9 @@
-use crate::askcode::{AskCodeOrValue, AskCodeReducer};
-use crate::reduce::{ParseError, Parser};
+use crate::askcode::{
+    AskCodeOrValue, AskCodeReducer, SpannedAskCodeOrValue, SpannedAskCodeReducer,
+};
+use crate::askscript::AskScriptParser;
+use crate::reduce::{ParseError, Parser, ParserOptions};
+use crate::trivia::Comment;
 
 pub fn parse(code: String, logging: bool) -> Result<AskCodeOrValue, ParseError> {
     let mut parser = Parser::new(code, None, logging);
@@ -7,11 +11,65 @@ pub fn parse(code: String, logging: bool) -> Result<AskCodeOrValue, ParseError>
     parser.reduce(ask_code_reducer)
 }
 
+/// Parses `code` within the limits of `options`, for untrusted programs.
+pub fn parse_with_options(
+    code: String,
+    options: ParserOptions,
+    logging: bool,
+) -> Result<AskCodeOrValue, ParseError> {
+    let mut parser = Parser::with_options(code, options, logging);
+    parser.reduce(AskCodeReducer {})
+}
+
+pub fn parse_with_spans(code: String, logging: bool) -> Result<SpannedAskCodeOrValue, ParseError> {
+    let mut parser = Parser::new(code, None, logging);
+    parser.reduce(SpannedAskCodeReducer {})
+}
+
+/// Parses `code` with spans, also returning its comments so that a formatter
+/// can put them back next to the nodes they belong to.
+pub fn parse_with_comments(
+    code: String,
+    logging: bool,
+) -> Result<(SpannedAskCodeOrValue, Vec<Comment>), ParseError> {
+    let mut parser = Parser::new(code, None, logging);
+    parser.reduce_with_comments(SpannedAskCodeReducer {})
+}
+
+pub fn parse_recovering(code: String, logging: bool) -> (Option<AskCodeOrValue>, Vec<ParseError>) {
+    let mut parser = Parser::new(code, None, logging);
+    parser.reduce_recovering(AskCodeReducer {})
+}
+
+pub fn parse_askscript(code: String, logging: bool) -> Result<AskCodeOrValue, ParseError> {
+    let mut parser = AskScriptParser::new(code, None, logging);
+    let ask_code_reducer = AskCodeReducer {};
+    parser.reduce(ask_code_reducer)
+}
+
+pub fn parse_askscript_with_options(
+    code: String,
+    options: ParserOptions,
+    logging: bool,
+) -> Result<AskCodeOrValue, ParseError> {
+    let mut parser = AskScriptParser::with_options(code, options, logging);
+    parser.reduce(AskCodeReducer {})
+}
+
+pub fn parse_askscript_with_spans(
+    code: String,
+    logging: bool,
+) -> Result<SpannedAskCodeOrValue, ParseError> {
+    let mut parser = AskScriptParser::new(code, None, logging);
+    parser.reduce(SpannedAskCodeReducer {})
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::askcode::AskCode;
-    use crate::reduce::ParseError;
+    use crate::reduce::{ParseError, DEFAULT_MAX_DEPTH};
+    use crate::trivia::CommentKind;
     use crate::value::Value;
 
     #[test]
@@ -40,6 +98,29 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_string_escapes() {
+        let string = |code: &str| match parse(code.to_string(), false) {
+            Ok(AskCodeOrValue::Value(Value::String(string))) => string,
+            repl => panic!("expected a string, got {:?}", repl),
+        };
+        assert_eq!(string(r"'a\nb\tc'"), "a\nb\tc");
+        assert_eq!(string(r"'it\'s'"), "it's");
+        assert_eq!(string(r#""say \"hi\"""#), "say \"hi\"");
+        assert_eq!(string(r"'back\\slash'"), "back\\slash");
+        assert_eq!(string(r"'\u{48}\u{1F600}'"), "H😀");
+        assert_eq!(
+            parse(r"'\q'".to_string(), false).unwrap_err().to_string(),
+            "1:2: unknown escape sequence `\\q`"
+        );
+        assert_eq!(
+            parse(r"'\u{D800}'".to_string(), false)
+                .unwrap_err()
+                .to_string(),
+            "1:2: `D800` is not a valid unicode character"
+        );
+    }
+
     #[test]
     fn test_int_number() {
         let repl = parse("4".to_string(), true);
@@ -60,6 +141,62 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_number_literals() {
+        let number = |code: &str| parse(code.to_string(), false).unwrap();
+        assert_eq!(
+            number("-1_000"),
+            AskCodeOrValue::new_value(Value::number("-1000".to_string()))
+        );
+        assert_eq!(
+            number("1e6"),
+            AskCodeOrValue::new_value(Value::number("1e6".to_string()))
+        );
+        assert_eq!(
+            number("0x1F"),
+            AskCodeOrValue::new_value(Value::number("31".to_string()))
+        );
+        assert_eq!(
+            parse("1..2".to_string(), false).unwrap_err().to_string(),
+            "1:1: invalid number literal `1..2`: expected digits, found `.`"
+        );
+        assert_eq!(
+            parse("max(1, -x)".to_string(), false)
+                .unwrap_err()
+                .to_string(),
+            "1:9: expected digit, found `x`"
+        );
+        assert_eq!(
+            parse("1-2-3".to_string(), false).unwrap_err().to_string(),
+            "1:2: expected end of input, found `-`"
+        );
+    }
+
+    #[test]
+    fn test_literal_keywords() {
+        let value = |value| AskCodeOrValue::new_value(value);
+        assert_eq!(
+            parse("f(true, false, null, nullable)".to_string(), false).unwrap(),
+            AskCodeOrValue::new_ask_code(AskCode::new(
+                "f".to_string(),
+                Some(vec![
+                    value(Value::Boolean(true)),
+                    value(Value::Boolean(false)),
+                    value(Value::Null),
+                    AskCodeOrValue::new_ask_code(AskCode::new("nullable".to_string(), None)),
+                ])
+            ))
+        );
+        assert_eq!(
+            parse("{true: null}".to_string(), false).unwrap(),
+            parse("object(list('true', null))".to_string(), false).unwrap()
+        );
+        assert_eq!(
+            parse("true(1)".to_string(), false).unwrap_err().to_string(),
+            "1:5: expected end of input, found `(`"
+        );
+    }
+
     #[test]
     fn test_expression() {
         let repl = parse("scorePerPhilosopher".to_string(), true);
@@ -82,4 +219,297 @@ mod tests {
         ));
         assert_eq!(repl.unwrap(), max);
     }
+
+    #[test]
+    fn test_object() {
+        assert_eq!(
+            parse("{ name: 'x', 'age': 3 }".to_string(), false).unwrap(),
+            parse("object(list('name','x'),list('age',3))".to_string(), false).unwrap()
+        );
+        assert_eq!(
+            parse("{}".to_string(), false).unwrap(),
+            AskCodeOrValue::new_ask_code(AskCode::new("object".to_string(), Some(vec![])))
+        );
+        assert_eq!(
+            parse("{ name 'x' }".to_string(), false)
+                .unwrap_err()
+                .to_string(),
+            "1:8: expected `:`, found `'`"
+        );
+        assert_eq!(
+            parse("{ a: 1: 2 }".to_string(), false)
+                .unwrap_err()
+                .to_string(),
+            "1:7: expected `,` or `}`, found `:`"
+        );
+        assert_eq!(
+            parse("{ [1]: 2 }".to_string(), false)
+                .unwrap_err()
+                .to_string(),
+            "1:3: expected key, found `[`"
+        );
+    }
+
+    #[test]
+    fn test_whitespace_and_comments() {
+        let code = "// total\r\nmax(\t1, /* two, */ 2,\u{a0}3\u{2003}) // end";
+        assert_eq!(
+            parse(code.to_string(), false).unwrap(),
+            parse("max(1,2,3)".to_string(), false).unwrap()
+        );
+        assert_eq!(
+            parse(" // nothing\n/* here */ ".to_string(), false),
+            Err(ParseError::EmptyProgram)
+        );
+        assert_eq!(
+            parse("max(1, /* 2".to_string(), false)
+                .unwrap_err()
+                .to_string(),
+            "1:8: expected `*/`, found end of input"
+        );
+    }
+
+    #[test]
+    fn test_comments_as_trivia() {
+        let code = "// total\nmax(1, /* two */ 2)";
+        let (value, comments) = parse_with_comments(code.to_string(), false).unwrap();
+        assert_eq!(
+            value.into_ask_code_or_value(),
+            parse("max(1,2)".to_string(), false).unwrap()
+        );
+        let comments: Vec<(CommentKind, &str, &str)> = comments
+            .iter()
+            .map(|comment| {
+                (
+                    comment.kind,
+                    comment.text.as_str(),
+                    comment.span.source(code),
+                )
+            })
+            .collect();
+        assert_eq!(
+            comments,
+            vec![
+                (CommentKind::Line, "// total", "// total"),
+                (CommentKind::Block, "/* two */", "/* two */"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_spans() {
+        let code = "max(\n  scorePerPhilosopher, 'a' )";
+        let repl = parse_with_spans(code.to_string(), true).unwrap();
+        assert_eq!(repl.span().source(code), code);
+        let params = match &repl {
+            SpannedAskCodeOrValue::AskCode(max) => max.params.clone().unwrap(),
+            _ => panic!("expecting max"),
+        };
+        assert_eq!(params[0].span().source(code), "scorePerPhilosopher");
+        assert_eq!(params[0].span().start.line, 2);
+        assert_eq!(params[0].span().start.column, 3);
+        assert_eq!(params[1].span().source(code), "'a'");
+        assert_eq!(
+            repl.into_ask_code_or_value(),
+            parse(code.to_string(), false).unwrap()
+        );
+
+        let code = "['çé', x]";
+        let repl = parse_with_spans(code.to_string(), false).unwrap();
+        let x = match &repl {
+            SpannedAskCodeOrValue::AskCode(list) => list.params.as_ref().unwrap()[1].span(),
+            _ => panic!("expecting list"),
+        };
+        assert_eq!((x.start.offset, x.start.column), (9, 8));
+        assert_eq!(x.source(code), "x");
+    }
+
+    #[test]
+    fn test_syntax_errors() {
+        let code = "ask(call(get('+'),1;2))";
+        let error = parse(code.to_string(), false).unwrap_err();
+        let diagnostic = error.diagnostic().unwrap();
+        assert_eq!((diagnostic.line(), diagnostic.column()), (1, 20));
+        assert_eq!(diagnostic.expected, vec!["`,`", "`)`"]);
+        assert_eq!(diagnostic.found, Some("`;`".to_string()));
+        assert_eq!(
+            error.render(code),
+            "error: expected `,` or `)`, found `;`\n --> 1:20\n  |\n1 | ask(call(get('+'),1;2))\n  |                    ^"
+        );
+        assert_eq!(
+            parse("max(\n  1\n".to_string(), false)
+                .unwrap_err()
+                .to_string(),
+            "3:1: expected `,` or `)`, found end of input"
+        );
+        assert_eq!(
+            parse("max(1,,2)".to_string(), false)
+                .unwrap_err()
+                .to_string(),
+            "1:7: expected expression, found `,`"
+        );
+        assert_eq!(
+            parse("'abc".to_string(), false).unwrap_err().to_string(),
+            "1:1: expected `'`, found end of input"
+        );
+        assert_eq!(
+            parse("max(1) 2".to_string(), false)
+                .unwrap_err()
+                .to_string(),
+            "1:8: expected end of input, found `2`"
+        );
+    }
+
+    fn error_node(message: &str) -> AskCodeOrValue {
+        AskCodeOrValue::new_ask_code(AskCode::new(
+            crate::reduce::ERROR_NODE.to_string(),
+            Some(vec![AskCodeOrValue::new_value(Value::String(
+                message.to_string(),
+            ))]),
+        ))
+    }
+
+    #[test]
+    fn test_recovering() {
+        let (code, errors) = parse_recovering("max(1 2, min(,3), 4".to_string(), false);
+        assert_eq!(
+            errors
+                .iter()
+                .map(|error| error.to_string())
+                .collect::<Vec<_>>(),
+            vec![
+                "1:7: expected `,` or `)`, found `2`",
+                "1:14: expected expression, found `,`",
+                "1:20: expected `,` or `)`, found end of input",
+            ]
+        );
+        let number = |number: &str| AskCodeOrValue::new_value(Value::number(number.to_string()));
+        let min = AskCodeOrValue::new_ask_code(AskCode::new(
+            "min".to_string(),
+            Some(vec![
+                error_node("1:14: expected expression, found `,`"),
+                number("3"),
+            ]),
+        ));
+        assert_eq!(
+            code.unwrap(),
+            AskCodeOrValue::new_ask_code(AskCode::new(
+                "max".to_string(),
+                Some(vec![number("1"), min, number("4")]),
+            ))
+        );
+    }
+
+    #[test]
+    fn test_recovering_valid_program() {
+        let code = "ask(call(get('+'),2,3))";
+        let (value, errors) = parse_recovering(code.to_string(), false);
+        assert!(errors.is_empty());
+        assert_eq!(value, parse(code.to_string(), false).ok());
+        let (value, errors) = parse_recovering("".to_string(), false);
+        assert_eq!((value, errors), (None, vec![ParseError::EmptyProgram]));
+    }
+
+    #[test]
+    fn test_recovering_nested_and_trailing_input() {
+        let (code, errors) = parse_recovering("f(g(1 ']' x), 2)) 3".to_string(), false);
+        assert_eq!(
+            errors
+                .iter()
+                .map(|error| error.to_string())
+                .collect::<Vec<_>>(),
+            vec![
+                "1:7: expected `,` or `)`, found `'`",
+                "1:17: expected end of input, found `)`",
+            ]
+        );
+        assert!(code.is_some());
+    }
+
+    #[test]
+    fn test_parser_limits() {
+        let limited = |code: &str, options: ParserOptions| {
+            parse_with_options(code.to_string(), options, false).err()
+        };
+        let nested = |depth: usize| format!("{}1{}", "[".repeat(depth), "]".repeat(depth));
+        let depth = ParserOptions {
+            max_depth: Some(3),
+            ..ParserOptions::default()
+        };
+        assert_eq!(limited(&nested(3), depth), None);
+        assert_eq!(
+            limited(&nested(4), depth),
+            Some(ParseError::ExceedMaxDepth(3))
+        );
+        let length = ParserOptions {
+            max_length: Some(8),
+            ..ParserOptions::default()
+        };
+        assert_eq!(limited("f(1, 2)", length), None);
+        assert_eq!(
+            limited("f(1, 2, 3)", length),
+            Some(ParseError::ExceedMaxLength(8))
+        );
+        let nodes = ParserOptions {
+            max_nodes: Some(6),
+            ..ParserOptions::default()
+        };
+        assert_eq!(limited("f(1, {a: x})", nodes), None);
+        assert_eq!(
+            limited("f(1, {a: x, b: y})", nodes),
+            Some(ParseError::ExceedMaxNodes(6))
+        );
+        let (_, errors) =
+            Parser::with_options(nested(4), depth, false).reduce_recovering(AskCodeReducer {});
+        assert_eq!(errors, vec![ParseError::ExceedMaxDepth(3)]);
+    }
+
+    #[test]
+    fn test_default_max_depth() {
+        let nested = |open: &str, close: &str, depth: usize| {
+            format!("{}1{}", open.repeat(depth), close.repeat(depth))
+        };
+        assert!(parse(nested("[", "]", DEFAULT_MAX_DEPTH), false).is_ok());
+        assert_eq!(
+            parse(nested("f(", ")", 100_000), false),
+            Err(ParseError::ExceedMaxDepth(DEFAULT_MAX_DEPTH))
+        );
+        // The outermost expression takes a level too.
+        assert!(parse_askscript(nested("(", ")", DEFAULT_MAX_DEPTH - 1), false).is_ok());
+        assert_eq!(
+            parse_askscript(nested("(", ")", 100_000), false),
+            Err(ParseError::ExceedMaxDepth(DEFAULT_MAX_DEPTH))
+        );
+    }
+
+    #[test]
+    fn test_depth_limit_prevents_stack_overflow() {
+        let options = ParserOptions {
+            max_depth: Some(16),
+            ..ParserOptions::default()
+        };
+        let nested = format!("{}1{}", "f(".repeat(100_000), ")".repeat(100_000));
+        assert_eq!(
+            parse_with_options(nested, options, false),
+            Err(ParseError::ExceedMaxDepth(16))
+        );
+        let nested = format!("{}1{}", "(".repeat(100_000), ")".repeat(100_000));
+        assert_eq!(
+            parse_askscript_with_options(nested, options, false),
+            Err(ParseError::ExceedMaxDepth(16))
+        );
+        let negated = format!("{}1", "-".repeat(100_000));
+        assert_eq!(
+            parse_askscript_with_options(negated, options, false),
+            Err(ParseError::ExceedMaxDepth(16))
+        );
+        let nested = format!(
+            "ask {{ query {{ {}1 }} }}",
+            "a { ".repeat(1000) + &"} ".repeat(1000)
+        );
+        assert_eq!(
+            parse_askscript_with_options(nested, options, false),
+            Err(ParseError::ExceedMaxDepth(16))
+        );
+    }
 }
@@ -0,0 +1,338 @@
+use crate::askcode::AskCodeOrValue;
+use crate::escape::escape;
+use crate::temporal::{format_date_time, format_duration};
+use crate::value::Value;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+
+/// How `print` lays out the source.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum PrintStyle {
+    /// Everything on one line without spaces: `ask(call(get('+'),1,2))`.
+    Compact,
+    /// Arguments go one per line, indented by two spaces, when one of them
+    /// has nested brackets; otherwise they stay on one line: `max(1, 2)`.
+    Pretty,
+}
+
+/// Renders `code` as AskCode source.
+///
+/// For every tree the parser produces, `parse(print(code, style))` gives the
+/// same tree back. `list` and `object` nodes are printed with the `[...]` and
+/// `{key: value}` literal syntax they are parsed from. Values the parser
+/// never produces are printed in the closest form it reads, e.g. `Int(2)` as
+/// `2`, which parses back as a `Number`, and a float that is not finite as
+/// the string `'NaN'`, `'Infinity'` or `'-Infinity'`.
+///
+/// Node names are printed as they are, since AskCode has no syntax to quote
+/// them. The `ERROR_NODE` that `parse_recovering` puts in place of invalid
+/// input is not an identifier, so a recovered tree does not parse again.
+pub fn print(code: &AskCodeOrValue, style: PrintStyle) -> String {
+    let mut printer = Printer {
+        style,
+        out: String::new(),
+    };
+    printer.code(code, 0);
+    printer.out
+}
+
+struct Printer {
+    style: PrintStyle,
+    out: String,
+}
+
+impl Printer {
+    fn code(&mut self, code: &AskCodeOrValue, depth: usize) {
+        match code {
+            AskCodeOrValue::Value(value) => self.value(value, depth),
+            AskCodeOrValue::AskCode(askcode) => match &askcode.params {
+                None => self.out.push_str(&askcode.name),
+                Some(params) => match askcode.name.as_str() {
+                    "list" => self.items("[", "]", params, depth, height, Self::code),
+                    "object" if params.iter().all(|param| entry(param).is_some()) => self.items(
+                        "{",
+                        "}",
+                        params,
+                        depth,
+                        |param| height(entry(param).unwrap().1),
+                        |printer, param, depth| {
+                            let (key, value) = entry(param).unwrap();
+                            printer.key(key);
+                            printer.code(value, depth);
+                        },
+                    ),
+                    name => {
+                        self.out.push_str(name);
+                        self.items("(", ")", params, depth, height, Self::code)
+                    }
+                },
+            },
+        }
+    }
+
+    fn value(&mut self, value: &Value, depth: usize) {
+        match value {
+            Value::Null => self.out.push_str("null"),
+            Value::Boolean(boolean) => self.out.push_str(&boolean.to_string()),
+            Value::Int(int) => self.out.push_str(&int.to_string()),
+            Value::Float(float) if float.is_finite() => self.out.push_str(&format!("{:?}", float)),
+            // AskCode has no literals for these either; the names are JSON's.
+            Value::Float(float) => {
+                let name = if float.is_nan() {
+                    "NaN"
+                } else if *float > 0.0 {
+                    "Infinity"
+                } else {
+                    "-Infinity"
+                };
+                self.out.push_str(&escape(name, '\''))
+            }
+            Value::BigInt(int) => self.out.push_str(&int.to_string()),
+            Value::Decimal(decimal) => self.out.push_str(&decimal.to_string()),
+            Value::Number(number) => self.out.push_str(&number.0),
+            // AskCode has no literals for these, so they print as strings.
+            Value::DateTime(date_time) => self
+                .out
+                .push_str(&escape(&format_date_time(date_time), '\'')),
+            Value::Duration(duration) => {
+                self.out.push_str(&escape(&format_duration(duration), '\''))
+            }
+            Value::Bytes(bytes) => self.out.push_str(&escape(&BASE64.encode(bytes), '\'')),
+            Value::String(string) => self.out.push_str(&escape(string, '\'')),
+            Value::List(list) => self.items("[", "]", list, depth, value_height, Self::value),
+            Value::Object(object) => {
+                let entries: Vec<(&String, &Value)> = object.iter().collect();
+                self.items(
+                    "{",
+                    "}",
+                    &entries,
+                    depth,
+                    |(_, value)| value_height(value),
+                    |printer, (key, value), depth| {
+                        printer.key(key);
+                        printer.value(value, depth);
+                    },
+                )
+            }
+        }
+    }
+
+    fn key(&mut self, key: &str) {
+        if is_identifier(key) {
+            self.out.push_str(key);
+        } else {
+            self.out.push_str(&escape(key, '\''));
+        }
+        self.out.push(':');
+        if self.style == PrintStyle::Pretty {
+            self.out.push(' ');
+        }
+    }
+
+    // Prints `items` between `open` and `close`. In pretty mode they go on
+    // separate lines when one of them has brackets nested in brackets.
+    fn items<T>(
+        &mut self,
+        open: &str,
+        close: &str,
+        items: &[T],
+        depth: usize,
+        height: impl Fn(&T) -> usize,
+        item: impl Fn(&mut Self, &T, usize),
+    ) {
+        self.out.push_str(open);
+        let multiline =
+            self.style == PrintStyle::Pretty && items.iter().any(|value| height(value) > 1);
+        for (index, value) in items.iter().enumerate() {
+            if index > 0 {
+                self.out.push(',');
+                if self.style == PrintStyle::Pretty && !multiline {
+                    self.out.push(' ');
+                }
+            }
+            if multiline {
+                self.newline(depth + 1);
+            }
+            item(self, value, depth + 1);
+        }
+        if multiline {
+            self.newline(depth);
+        }
+        self.out.push_str(close);
+    }
+
+    fn newline(&mut self, depth: usize) {
+        self.out.push('\n');
+        self.out.push_str(&"  ".repeat(depth));
+    }
+}
+
+// How deeply brackets nest when printing `code`.
+fn height(code: &AskCodeOrValue) -> usize {
+    match code {
+        AskCodeOrValue::Value(value) => value_height(value),
+        AskCodeOrValue::AskCode(askcode) => match &askcode.params {
+            None => 0,
+            Some(params) => 1 + params.iter().map(height).max().unwrap_or(0),
+        },
+    }
+}
+
+fn value_height(value: &Value) -> usize {
+    match value {
+        Value::List(list) => 1 + list.iter().map(value_height).max().unwrap_or(0),
+        Value::Object(object) => 1 + object.values().map(value_height).max().unwrap_or(0),
+        _ => 0,
+    }
+}
+
+// The key and value of an object entry, `list('key', value)`.
+fn entry(code: &AskCodeOrValue) -> Option<(&str, &AskCodeOrValue)> {
+    match code {
+        AskCodeOrValue::AskCode(askcode) if askcode.name == "list" => {
+            match askcode.params.as_deref() {
+                Some([AskCodeOrValue::Value(Value::String(key)), value]) => Some((key, value)),
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
+
+fn is_identifier(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars
+        .next()
+        .is_some_and(|char| char == '_' || char.is_ascii_alphabetic())
+        && chars.all(|char| char == '_' || char.is_ascii_alphanumeric())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::askcode::AskCode;
+    use crate::parse::{parse, parse_recovering};
+    use crate::reduce::RESERVED_WORDS;
+    use crate::value::Object;
+    use proptest::prelude::*;
+
+    fn parsed(code: &str) -> AskCodeOrValue {
+        parse(code.to_string(), false).unwrap()
+    }
+
+    #[test]
+    fn test_print_compact() {
+        let code = parsed("ask( call(get('+'), 1, -2.5e3), [x, 'it\\'s'], {a: 1, 'b c': f()} )");
+        assert_eq!(
+            print(&code, PrintStyle::Compact),
+            r"ask(call(get('+'),1,-2.5e3),[x,'it\'s'],{a:1,'b c':f()})"
+        );
+    }
+
+    #[test]
+    fn test_print_pretty() {
+        let code = parsed("ask(call(get('+'),1,2),query(node('a',f(get('a')))),{a:[1]})");
+        assert_eq!(
+            print(&code, PrintStyle::Pretty),
+            "ask(
+  call(get('+'), 1, 2),
+  query(
+    node(
+      'a',
+      f(get('a'))
+    )
+  ),
+  {a: [1]}
+)"
+        );
+        assert_eq!(print(&parsed("max(1, 2)"), PrintStyle::Pretty), "max(1, 2)");
+    }
+
+    #[test]
+    fn test_print_values() {
+        let mut object = Object::new();
+        object.insert("key".to_string(), Value::Float(2.0));
+        let value = Value::List(vec![
+            Value::Int(1),
+            Value::Object(object),
+            Value::Null,
+            Value::Float(f64::NAN),
+            Value::Float(f64::NEG_INFINITY),
+        ]);
+        let text = print(&AskCodeOrValue::Value(value), PrintStyle::Compact);
+        assert_eq!(text, "[1,{key:2.0},null,'NaN','-Infinity']");
+        assert!(parse(text, false).is_ok());
+    }
+
+    #[test]
+    fn test_print_recovered() {
+        let (code, errors) = parse_recovering("max(1, )".to_string(), false);
+        assert_eq!(errors.len(), 1);
+        let text = print(&code.unwrap(), PrintStyle::Compact);
+        assert_eq!(text, "max(1,$error('1:8: expected expression, found `)`'))");
+        assert!(parse(text, false).is_err());
+    }
+
+    fn ask_code() -> impl Strategy<Value = AskCodeOrValue> {
+        let identifier = "[_a-zA-Z][_a-zA-Z0-9]{0,8}"
+            .prop_filter("reserved words are literals", |name: &String| {
+                !RESERVED_WORDS.contains(&name.as_str())
+            });
+        let leaf = prop_oneof![
+            Just(AskCodeOrValue::Value(Value::Null)),
+            any::<bool>().prop_map(|boolean| AskCodeOrValue::Value(Value::Boolean(boolean))),
+            any::<String>().prop_map(|string| AskCodeOrValue::Value(Value::String(string))),
+            "-?[0-9]{1,12}(\\.[0-9]{1,6})?(e-?[0-9]{1,2})?"
+                .prop_map(|number| AskCodeOrValue::Value(Value::number(number))),
+            identifier
+                .clone()
+                .prop_map(|name| AskCodeOrValue::AskCode(AskCode::new(name, None))),
+        ];
+        leaf.prop_recursive(4, 32, 6, move |inner| {
+            prop_oneof![
+                (
+                    identifier.clone(),
+                    prop::collection::vec(inner.clone(), 0..6)
+                )
+                    .prop_map(|(name, params)| AskCodeOrValue::AskCode(
+                        AskCode::new(name, Some(params))
+                    )),
+                prop::collection::vec((any::<String>(), inner), 0..4).prop_map(|entries| {
+                    let entries = entries
+                        .into_iter()
+                        .map(|(key, value)| {
+                            let key = AskCodeOrValue::Value(Value::String(key));
+                            AskCodeOrValue::AskCode(AskCode::new(
+                                "list".to_string(),
+                                Some(vec![key, value]),
+                            ))
+                        })
+                        .collect();
+                    AskCodeOrValue::AskCode(AskCode::new("object".to_string(), Some(entries)))
+                }),
+            ]
+        })
+    }
+
+    // Numbers compare by value, so the printed text is compared too, to catch
+    // a literal such as `1.0` coming back as `1`.
+    fn round_trip(code: AskCodeOrValue, style: PrintStyle) -> Result<(), TestCaseError> {
+        let text = print(&code, style);
+        let parsed = parse(text.clone(), false);
+        prop_assert_eq!(parsed.as_ref(), Ok(&code));
+        prop_assert_eq!(print(&parsed.unwrap(), style), text);
+        Ok(())
+    }
+
+    proptest! {
+        #[test]
+        fn prop_compact_round_trips(code in ask_code()) {
+            round_trip(code, PrintStyle::Compact)?;
+        }
+
+        #[test]
+        fn prop_pretty_round_trips(code in ask_code()) {
+            round_trip(code, PrintStyle::Pretty)?;
+        }
+    }
+}
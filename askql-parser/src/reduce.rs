@@ -1,80 +1,307 @@
+use crate::diagnostic::Diagnostic;
+use crate::escape::unescape;
+use crate::number::scan_number;
+use crate::span::{SourceMap, Span, SpanReducer};
+use crate::trivia::{Comment, CommentKind};
 use crate::value::{Number, Value};
 use log::info;
-use regex::Regex;
+use std::fmt;
+
+/// Name of the node a recovering parse puts where it skipped invalid input.
+/// It is not a valid identifier, so it cannot clash with a resource.
+pub const ERROR_NODE: &str = "$error";
+
+/// Words that are read as literals rather than identifiers, so they cannot
+/// name a resource.
+pub const RESERVED_WORDS: [&str; 3] = ["true", "false", "null"];
+
+// The value of the literal `name`, if it is one of the reserved words.
+fn literal(name: &str) -> Option<Value> {
+    match name {
+        "true" => Some(Value::Boolean(true)),
+        "false" => Some(Value::Boolean(false)),
+        "null" => Some(Value::Null),
+        _ => None,
+    }
+}
 
 pub trait Reducer<T> {
     fn node(&self, name: String, children: Option<Vec<T>>) -> T;
     fn id(&self, name: String) -> T;
     fn value(&self, value: Value) -> T;
+    fn error(&self, error: &ParseError) -> T {
+        self.node(
+            ERROR_NODE.to_string(),
+            Some(vec![self.value(Value::String(error.to_string()))]),
+        )
+    }
+}
+
+/// Limits for parsing untrusted programs. `None` means unlimited, which is
+/// the default for all but `max_depth`.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct ParserOptions {
+    /// Parser steps before giving up with `ParseError::ExceedMaxStpes`.
+    pub max_steps: Option<usize>,
+    /// How deeply brackets may nest. It is checked before recursing, so deep
+    /// input fails with `ParseError::ExceedMaxDepth` instead of overflowing
+    /// the stack; a level can take tens of KiB of stack in debug builds, so
+    /// keep it well below what the thread can hold.
+    /// Defaults to `DEFAULT_MAX_DEPTH`.
+    pub max_depth: Option<usize>,
+    /// Source length in bytes, checked before parsing starts.
+    pub max_length: Option<usize>,
+    /// Number of nodes and values in the parsed program.
+    pub max_nodes: Option<usize>,
+}
+
+/// Nesting that both parsers handle within the 2 MiB stack of a spawned
+/// thread in debug builds, where an AskScript parenthesis takes about 48 KiB.
+pub const DEFAULT_MAX_DEPTH: usize = 32;
+
+impl Default for ParserOptions {
+    fn default() -> Self {
+        Self {
+            max_steps: None,
+            max_depth: Some(DEFAULT_MAX_DEPTH),
+            max_length: None,
+            max_nodes: None,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Eq, PartialEq)]
 pub enum ParseError {
     ExceedMaxStpes(usize),
-    Expecting(char),
-    Unknown(String),
+    ExceedMaxDepth(usize),
+    ExceedMaxLength(usize),
+    ExceedMaxNodes(usize),
+    Syntax(Diagnostic),
     EmptyProgram,
 }
 
+impl ParseError {
+    /// Whether parsing stopped at one of the `ParserOptions` limits rather
+    /// than at invalid input. Such errors are never recovered from.
+    pub fn is_limit(&self) -> bool {
+        matches!(
+            self,
+            ParseError::ExceedMaxStpes(_)
+                | ParseError::ExceedMaxDepth(_)
+                | ParseError::ExceedMaxLength(_)
+                | ParseError::ExceedMaxNodes(_)
+        )
+    }
+
+    pub fn diagnostic(&self) -> Option<&Diagnostic> {
+        match self {
+            ParseError::Syntax(diagnostic) => Some(diagnostic),
+            _ => None,
+        }
+    }
+
+    /// Renders the error for humans, with a code frame pointing into `code`
+    /// for syntax errors.
+    pub fn render(&self, code: &str) -> String {
+        match self {
+            ParseError::Syntax(diagnostic) => diagnostic.render(code),
+            error => format!("error: {}", error),
+        }
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseError::ExceedMaxStpes(steps) => {
+                write!(f, "parser exceeded the maximum of {} steps", steps)
+            }
+            ParseError::ExceedMaxDepth(depth) => {
+                write!(f, "program nests deeper than the maximum of {}", depth)
+            }
+            ParseError::ExceedMaxLength(length) => {
+                write!(f, "program is longer than the maximum of {} bytes", length)
+            }
+            ParseError::ExceedMaxNodes(nodes) => {
+                write!(f, "program has more than the maximum of {} nodes", nodes)
+            }
+            ParseError::Syntax(diagnostic) => write!(f, "{}", diagnostic),
+            ParseError::EmptyProgram => write!(f, "empty program"),
+        }
+    }
+}
+
 pub struct Parser {
-    code: Vec<char>,
+    code: String,
+    source_map: SourceMap,
+    // Byte offset into `code`.
     index: usize,
     steps: usize,
-    id_regex: Regex,
-    number_regex: Regex,
-    stop_after_steps: Option<usize>,
+    depth: usize,
+    nodes: usize,
+    options: ParserOptions,
     logging: bool,
+    recovering: bool,
+    errors: Vec<ParseError>,
+    comments: Option<Vec<Comment>>,
 }
 
 impl Parser {
     pub fn new(code: String, stop_after_steps: Option<usize>, logging: bool) -> Self {
+        let options = ParserOptions {
+            max_steps: stop_after_steps,
+            ..ParserOptions::default()
+        };
+        Self::with_options(code, options, logging)
+    }
+
+    pub fn with_options(code: String, options: ParserOptions, logging: bool) -> Self {
         Self {
-            code: code.chars().collect(),
+            source_map: SourceMap::new(&code),
+            code,
             index: 0,
             steps: 0,
-            number_regex: Regex::new("[0-9.-]").unwrap(),
-            id_regex: Regex::new("[_a-zA-Z0-9]").unwrap(),
-            stop_after_steps,
+            depth: 0,
+            nodes: 0,
+            options,
             logging,
+            recovering: false,
+            errors: Vec::new(),
+            comments: None,
         }
     }
 }
 
-#[derive(Debug, Copy, Clone)]
-enum Delta {
-    Subtract(usize),
-    None,
-}
-
-impl Default for Delta {
-    fn default() -> Self {
-        Delta::None
-    }
-}
-
 impl Parser {
     pub fn reduce<R, T>(&mut self, reducer: R) -> Result<T, ParseError>
     where
-        R: Reducer<T>,
+        R: SpanReducer<T>,
         T: std::fmt::Debug,
     {
-        if self.code.is_empty() {
+        if let Some(max_length) = self.options.max_length {
+            if self.code.len() > max_length {
+                return Err(ParseError::ExceedMaxLength(max_length));
+            }
+        }
+        self.whitespace()?;
+        if self.index == self.code.len() {
             return Err(ParseError::EmptyProgram);
         }
         self.program(&reducer)
     }
 
-    fn delta(&self, delta: Delta) -> usize {
-        use Delta::*;
-        match delta {
-            Subtract(d) => self.index.checked_sub(d).unwrap(),
-            None => self.index,
+    /// Parses the whole program even when it has syntax errors: invalid
+    /// input is skipped up to the next `,` or closing delimiter and replaced
+    /// by an error node. Returns the partial tree, if any, with every error.
+    pub fn reduce_recovering<R, T>(&mut self, reducer: R) -> (Option<T>, Vec<ParseError>)
+    where
+        R: SpanReducer<T>,
+        T: std::fmt::Debug,
+    {
+        self.recovering = true;
+        let value = self.reduce(reducer);
+        let mut errors = std::mem::take(&mut self.errors);
+        match value {
+            Ok(value) => (Some(value), errors),
+            Err(error) => {
+                errors.push(error);
+                (None, errors)
+            }
+        }
+    }
+
+    /// Parses the program like `reduce`, also returning its comments in
+    /// source order.
+    pub fn reduce_with_comments<R, T>(
+        &mut self,
+        reducer: R,
+    ) -> Result<(T, Vec<Comment>), ParseError>
+    where
+        R: SpanReducer<T>,
+        T: std::fmt::Debug,
+    {
+        self.comments = Some(Vec::new());
+        let value = self.reduce(reducer)?;
+        Ok((value, self.comments.take().unwrap_or_default()))
+    }
+
+    fn recover<U, R>(
+        &mut self,
+        reducer: &R,
+        error: ParseError,
+        start: usize,
+        close: Option<u8>,
+    ) -> Result<U, ParseError>
+    where
+        R: SpanReducer<U>,
+    {
+        if !self.recovering || error.is_limit() {
+            return Err(error);
+        }
+        self.synchronize(close);
+        let node = reducer.error(&error, self.span(start));
+        self.errors.push(error);
+        Ok(node)
+    }
+
+    // Skips to the next `,` or `close` that is not nested inside brackets,
+    // strings or comments.
+    fn synchronize(&mut self, close: Option<u8>) {
+        let mut depth = 0;
+        while self.index < self.code.len() {
+            let byte = self.code.as_bytes()[self.index];
+            match byte {
+                b'/' if self.comment().is_ok_and(|skipped| skipped) => continue,
+                b',' if depth == 0 => return,
+                byte if depth == 0 && Some(byte) == close => return,
+                b'(' | b'[' | b'{' => depth += 1,
+                b')' | b']' | b'}' if depth > 0 => depth -= 1,
+                b'\'' | b'"' => {
+                    let bytes = self.code.as_bytes();
+                    self.index += 1;
+                    while self.index < bytes.len() && bytes[self.index] != byte {
+                        if bytes[self.index] == b'\\' {
+                            self.index += 1;
+                        }
+                        self.index += 1;
+                    }
+                }
+                _ => {}
+            }
+            self.index += 1;
         }
+        self.index = self.index.min(self.code.len());
+    }
+
+    fn span(&self, start: usize) -> Span {
+        self.source_map.span(start, self.index)
+    }
+
+    fn next_char(&self) -> Option<char> {
+        self.code[self.index..].chars().next()
+    }
+
+    fn found(&self) -> Option<String> {
+        self.next_char().map(|char| format!("`{}`", char))
+    }
+
+    fn expecting(&self, expected: &[&str]) -> ParseError {
+        let end = self.index + self.next_char().map_or(0, char::len_utf8);
+        ParseError::Syntax(Diagnostic::expecting(
+            expected
+                .iter()
+                .map(|expected| expected.to_string())
+                .collect(),
+            self.found(),
+            self.source_map.span(self.index, end),
+        ))
     }
 
-    fn step(&mut self, message: String) -> Result<(), ParseError> {
+    // Takes the message as `format_args!` so that it is only formatted when
+    // logging.
+    fn step(&mut self, message: fmt::Arguments) -> Result<(), ParseError> {
         self.steps += 1;
-        match self.stop_after_steps {
+        match self.options.max_steps {
             Some(after_steps) if self.steps > after_steps => {
                 Err(ParseError::ExceedMaxStpes(after_steps))
             }
@@ -88,82 +315,144 @@ impl Parser {
         }
     }
 
-    fn is_at(&self, char: char, delta: Delta) -> bool {
-        let pos = self.delta(delta);
-        return pos < self.code.len() && self.code[pos] == char;
+    // Counts `count` more nodes of the program.
+    fn count_nodes(&mut self, count: usize) -> Result<(), ParseError> {
+        self.nodes += count;
+        match self.options.max_nodes {
+            Some(max_nodes) if self.nodes > max_nodes => Err(ParseError::ExceedMaxNodes(max_nodes)),
+            _ => Ok(()),
+        }
+    }
+
+    fn is_at(&self, byte: u8) -> bool {
+        self.code.as_bytes().get(self.index) == Some(&byte)
+    }
+
+    fn is_at_id(&self) -> bool {
+        self.code
+            .as_bytes()
+            .get(self.index)
+            .is_some_and(|byte| byte.is_ascii_alphanumeric() || *byte == b'_')
     }
 
-    fn is_at_regex(&self, regex: &Regex, delta: Delta) -> bool {
-        let pos = self.delta(delta);
-        return pos < self.code.len() && regex.is_match(&self.code[pos].to_string());
+    fn is_at_digit(&self) -> bool {
+        self.code
+            .as_bytes()
+            .get(self.index)
+            .is_some_and(u8::is_ascii_digit)
     }
 
-    fn process(&mut self, char: char) -> Result<usize, ParseError> {
-        if !self.is_at(char, Delta::None) {
-            return Err(ParseError::Expecting(char));
+    fn process(&mut self, byte: u8) -> Result<usize, ParseError> {
+        if !self.is_at(byte) {
+            return Err(self.expecting(&[&format!("`{}`", byte as char)]));
         }
-        self.step(format!("process({})", char))?;
+        self.step(format_args!("process({})", byte as char))?;
         self.index += 1;
         Ok(self.index)
     }
 
-    fn whitespace(&mut self) {
-        while self.is_at(' ', Delta::None) || self.is_at('\n', Delta::None) {
-            self.index += 1
+    // Skips whitespace and comments.
+    fn whitespace(&mut self) -> Result<(), ParseError> {
+        loop {
+            let rest = &self.code[self.index..];
+            self.index += rest.len() - rest.trim_start().len();
+            if !self.comment()? {
+                return Ok(());
+            }
         }
     }
 
-    fn id(&mut self) -> Result<&[char], ParseError> {
-        self.whitespace();
-        self.step("id".to_string())?;
+    // Skips the comment at the current index, if any, keeping it when
+    // comments are retained.
+    fn comment(&mut self) -> Result<bool, ParseError> {
         let start = self.index;
-        while self.index < self.code.len() && self.is_at_regex(&self.id_regex, Delta::None) {
+        let rest = &self.code[start..];
+        let kind = if rest.starts_with("//") {
+            self.index += rest.find('\n').unwrap_or(rest.len());
+            CommentKind::Line
+        } else if let Some(comment) = rest.strip_prefix("/*") {
+            match comment.find("*/") {
+                Some(end) => self.index += end + 4,
+                None => {
+                    self.index = self.code.len();
+                    return Err(ParseError::Syntax(Diagnostic::expecting(
+                        vec!["`*/`".to_string()],
+                        None,
+                        self.span(start),
+                    )));
+                }
+            }
+            CommentKind::Block
+        } else {
+            return Ok(false);
+        };
+        if let Some(comments) = &mut self.comments {
+            comments.push(Comment {
+                kind,
+                text: self.code[start..self.index].to_string(),
+                span: self.source_map.span(start, self.index),
+            });
+        }
+        Ok(true)
+    }
+
+    fn id(&mut self) -> Result<&str, ParseError> {
+        self.whitespace()?;
+        self.step(format_args!("id"))?;
+        let start = self.index;
+        while self.is_at_id() {
             self.index += 1;
         }
         if self.index == start {
-            return Err(ParseError::Unknown(format!(
-                "id error at index: {}",
-                self.index
-            )));
+            return Err(self.expecting(&["expression"]));
         }
-        return Ok(self.code.get(start..self.index).unwrap());
+        Ok(&self.code[start..self.index])
     }
 
     fn program<U, R>(&mut self, reducer: &R) -> Result<U, ParseError>
     where
-        R: Reducer<U>,
+        R: SpanReducer<U>,
         U: std::fmt::Debug,
     {
-        let value = self.expression(reducer);
-        self.whitespace();
+        let start = self.index;
+        let value = match self.expression(reducer) {
+            Ok(value) => value,
+            Err(error) => self.recover(reducer, error, start, None)?,
+        };
+        self.whitespace()?;
         if self.index < self.code.len() {
-            return Err(ParseError::Unknown(format!(
-                "program error at index: {}",
-                self.index
-            )));
+            let error = self.expecting(&["end of input"]);
+            if !self.recovering {
+                return Err(error);
+            }
+            self.errors.push(error);
         }
-        value
+        Ok(value)
     }
 
     fn expression<U, R>(&mut self, reducer: &R) -> Result<U, ParseError>
     where
-        R: Reducer<U>,
+        R: SpanReducer<U>,
         U: std::fmt::Debug,
     {
-        self.whitespace();
-        self.step("expression".to_string())?;
+        self.whitespace()?;
+        self.step(format_args!("expression"))?;
+        self.count_nodes(1)?;
 
-        if self.is_at('"', Delta::None) || self.is_at('\'', Delta::None) {
+        if self.is_at(b'"') || self.is_at(b'\'') {
             return self.string(reducer);
         }
-        if self.is_at_regex(&self.number_regex, Delta::None) {
+        if self.is_at(b'-') || self.is_at_digit() {
             return self.number(reducer);
         }
-        if self.is_at('[', Delta::None) {
-            return self.expression_list(reducer, "list".to_string(), '[', ']', ',', ',');
+        let start = self.index;
+        if self.is_at(b'[') {
+            let values = self.expression_list(reducer, b'[', b']', Self::expression)?;
+            return Ok(reducer.node("list".to_string(), Some(values), self.span(start)));
         }
-        if self.is_at('{', Delta::None) {
-            return self.expression_list(reducer, "object".to_string(), '{', '}', ',', ':');
+        if self.is_at(b'{') {
+            let entries = self.expression_list(reducer, b'{', b'}', Self::entry)?;
+            return Ok(reducer.node("object".to_string(), Some(entries), self.span(start)));
         }
         return self.call(reducer);
     }
@@ -171,97 +460,191 @@ impl Parser {
     fn expression_list<U, R>(
         &mut self,
         reducer: &R,
-        name: String,
-        open_char: char,
-        close_char: char,
-        separator: char,
-        odd_separator: char,
-    ) -> Result<U, ParseError>
+        open: u8,
+        close: u8,
+        item: fn(&mut Self, &R) -> Result<U, ParseError>,
+    ) -> Result<Vec<U>, ParseError>
     where
-        R: Reducer<U>,
+        R: SpanReducer<U>,
         U: std::fmt::Debug,
     {
-        self.process(open_char)?;
-        self.whitespace();
+        if let Some(max_depth) = self.options.max_depth {
+            if self.depth >= max_depth {
+                return Err(ParseError::ExceedMaxDepth(max_depth));
+            }
+        }
+        self.depth += 1;
+        let values = self.list_items(reducer, open, close, item);
+        self.depth -= 1;
+        values
+    }
+
+    fn list_items<U, R>(
+        &mut self,
+        reducer: &R,
+        open: u8,
+        close: u8,
+        item: fn(&mut Self, &R) -> Result<U, ParseError>,
+    ) -> Result<Vec<U>, ParseError>
+    where
+        R: SpanReducer<U>,
+        U: std::fmt::Debug,
+    {
+        self.process(open)?;
+        self.whitespace()?;
         let mut values: Vec<U> = Vec::new();
-        let start = self.index;
-        while self.index < self.code.len() && !self.is_at(close_char, Delta::None) {
-            let value = self.expression(reducer)?;
-            self.step(format!("list item {:#?}", value))?;
+        while self.index < self.code.len() && !self.is_at(close) {
+            self.whitespace()?;
+            let item_start = self.index;
+            let value = match item(self, reducer) {
+                Ok(value) => value,
+                Err(error) => self.recover(reducer, error, item_start, Some(close))?,
+            };
+            self.step(format_args!("list item {:#?}", value))?;
             values.push(value);
-            self.whitespace();
-            if !self.is_at(close_char, Delta::None) {
-                self.process(if (self.index - start) % 2 == 0 {
-                    odd_separator
-                } else {
-                    separator
-                })?;
+            self.whitespace()?;
+            if !self.is_at(close) {
+                if !self.is_at(b',') {
+                    let error = self.expecting(&["`,`", &format!("`{}`", close as char)]);
+                    if !self.recovering {
+                        return Err(error);
+                    }
+                    self.errors.push(error);
+                    self.synchronize(Some(close));
+                    if self.index == self.code.len() {
+                        return Ok(values);
+                    }
+                    if self.is_at(b',') {
+                        self.process(b',')?;
+                    }
+                    continue;
+                }
+                self.process(b',')?;
             }
         }
-        self.process(close_char)?;
-        Ok(reducer.node(name, Some(values)))
+        if self.recovering && self.index == self.code.len() {
+            let error = self.expecting(&[&format!("`{}`", close as char)]);
+            self.errors.push(error);
+            return Ok(values);
+        }
+        self.process(close)?;
+        Ok(values)
+    }
+
+    // An object entry `key: value`, where the key is an identifier or a
+    // string, reduced to the pair `list('key', value)`.
+    fn entry<U, R>(&mut self, reducer: &R) -> Result<U, ParseError>
+    where
+        R: SpanReducer<U>,
+        U: std::fmt::Debug,
+    {
+        self.whitespace()?;
+        self.step(format_args!("entry"))?;
+        // The key and the `list` node holding the pair.
+        self.count_nodes(2)?;
+        let start = self.index;
+        let key = if self.is_at(b'"') || self.is_at(b'\'') {
+            self.string(reducer)?
+        } else if self.is_at_id() {
+            let name = self.id()?.to_string();
+            reducer.value(Value::String(name), self.span(start))
+        } else {
+            return Err(self.expecting(&["key"]));
+        };
+        self.whitespace()?;
+        self.process(b':')?;
+        let value = self.expression(reducer)?;
+        Ok(reducer.node("list".to_string(), Some(vec![key, value]), self.span(start)))
     }
 
     fn number<U, R>(&mut self, reducer: &R) -> Result<U, ParseError>
     where
-        R: Reducer<U>,
+        R: SpanReducer<U>,
         U: std::fmt::Debug,
     {
-        self.step("number".to_string())?;
+        self.step(format_args!("number"))?;
         let start = self.index;
-        while self.index < self.code.len() && self.is_at_regex(&self.number_regex, Delta::None) {
+        let mut number = String::new();
+        if self.is_at(b'-') {
+            number.push('-');
             self.index += 1;
         }
-        let end = self.index;
-        Ok(reducer.value(Value::Number(Number(
-            self.code.get(start..end).unwrap().iter().collect(),
-        ))))
+        if !self.is_at_digit() {
+            return Err(self.expecting(&["digit"]));
+        }
+        let (digits, end) = scan_number(&self.code, self.index).map_err(|error| {
+            ParseError::Syntax(Diagnostic::new(
+                error.message,
+                self.source_map.span(start, error.end),
+            ))
+        })?;
+        number.push_str(&digits);
+        self.index = end;
+        Ok(reducer.value(Value::Number(Number(number)), self.span(start)))
     }
 
     fn string<U, R>(&mut self, reducer: &R) -> Result<U, ParseError>
     where
-        R: Reducer<U>,
+        R: SpanReducer<U>,
         U: std::fmt::Debug,
     {
-        self.step("string".to_string())?;
-        let quote = self.code[self.index];
+        self.step(format_args!("string"))?;
+        let node_start = self.index;
+        let quote = self.code.as_bytes()[self.index];
         self.process(quote)?;
-        let start = self.index;
-        while self.index < self.code.len()
-            && !(!self.is_at('\\', Delta::Subtract(1)) && self.is_at(quote, Delta::None))
-        {
-            self.index += 1;
+        let mut string = String::new();
+        loop {
+            let bytes = self.code.as_bytes();
+            let run_start = self.index;
+            while self.index < bytes.len()
+                && bytes[self.index] != quote
+                && bytes[self.index] != b'\\'
+            {
+                self.index += 1;
+            }
+            string.push_str(&self.code[run_start..self.index]);
+            if !self.is_at(b'\\') {
+                break;
+            }
+            let (char, end) = unescape(&self.code, self.index).map_err(|error| {
+                ParseError::Syntax(Diagnostic::new(
+                    error.message,
+                    self.source_map.span(self.index, error.end),
+                ))
+            })?;
+            string.push(char);
+            self.index = end;
         }
         if self.index == self.code.len() {
-            return Err(ParseError::Unknown(format!(
-                "string error at index: {}",
-                self.index
+            return Err(ParseError::Syntax(Diagnostic::expecting(
+                vec![format!("`{}`", quote as char)],
+                None,
+                self.span(node_start),
             )));
         }
-        let end = self.index;
         self.process(quote)?;
-        Ok(reducer.value(Value::String(
-            self.code
-                .get(start..end)
-                .expect("Error in String")
-                .iter()
-                .collect(),
-        )))
+        Ok(reducer.value(Value::String(string), self.span(node_start)))
     }
 
     fn call<U, R>(&mut self, reducer: &R) -> Result<U, ParseError>
     where
-        R: Reducer<U>,
+        R: SpanReducer<U>,
         U: std::fmt::Debug,
     {
-        self.whitespace();
-        let name: String = self.id()?.iter().collect();
-        self.whitespace();
-        if !self.is_at('(', Delta::None) {
-            return Ok(reducer.id(name));
+        self.whitespace()?;
+        let start = self.index;
+        let name = self.id()?.to_string();
+        let span = self.span(start);
+        if let Some(value) = literal(&name) {
+            return Ok(reducer.value(value, span));
+        }
+        self.whitespace()?;
+        if !self.is_at(b'(') {
+            return Ok(reducer.id(name, span));
         }
-        self.whitespace();
-        self.expression_list(reducer, name, '(', ')', ',', ',')
+        self.whitespace()?;
+        let values = self.expression_list(reducer, b'(', b')', Self::expression)?;
+        Ok(reducer.node(name, Some(values), self.span(start)))
     }
 }
 
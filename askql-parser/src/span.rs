@@ -0,0 +1,150 @@
+use crate::reduce::{ParseError, Reducer};
+use crate::value::Value;
+
+/// A location in the source: byte `offset` plus 1-based `line` and `column`
+/// (columns count characters, not bytes).
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash, Serialize)]
+pub struct Position {
+    pub offset: usize,
+    pub line: usize,
+    pub column: usize,
+}
+
+impl Default for Position {
+    fn default() -> Self {
+        Self {
+            offset: 0,
+            line: 1,
+            column: 1,
+        }
+    }
+}
+
+/// The half-open source range `start..end` a parsed node was read from.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash, Default, Serialize)]
+pub struct Span {
+    pub start: Position,
+    pub end: Position,
+}
+
+impl Span {
+    pub fn new(start: Position, end: Position) -> Self {
+        Self { start, end }
+    }
+
+    pub fn to(&self, other: Span) -> Span {
+        Span::new(self.start, other.end)
+    }
+
+    pub fn source<'a>(&self, code: &'a str) -> &'a str {
+        &code[self.start.offset..self.end.offset]
+    }
+}
+
+/// Maps the parsers' byte offsets to `Position`s.
+pub struct SourceMap {
+    lines: Vec<Line>,
+    len: usize,
+}
+
+struct Line {
+    start: usize,
+    // Byte offsets of the line's characters, kept only for non-ASCII lines
+    // where columns and byte offsets differ.
+    chars: Option<Vec<usize>>,
+}
+
+impl SourceMap {
+    pub fn new(code: &str) -> Self {
+        let lines = code
+            .split('\n')
+            .scan(0, |start, line| {
+                let line_start = *start;
+                *start += line.len() + 1;
+                let chars = if line.is_ascii() {
+                    None
+                } else {
+                    Some(
+                        line.char_indices()
+                            .map(|(offset, _)| line_start + offset)
+                            .collect(),
+                    )
+                };
+                Some(Line {
+                    start: line_start,
+                    chars,
+                })
+            })
+            .collect();
+        Self {
+            lines,
+            len: code.len(),
+        }
+    }
+
+    pub fn position(&self, offset: usize) -> Position {
+        let offset = offset.min(self.len);
+        let line = self.lines.partition_point(|line| line.start <= offset) - 1;
+        let column = match &self.lines[line].chars {
+            None => offset - self.lines[line].start,
+            Some(chars) => chars.partition_point(|&start| start < offset),
+        };
+        Position {
+            offset,
+            line: line + 1,
+            column: column + 1,
+        }
+    }
+
+    pub fn span(&self, start: usize, end: usize) -> Span {
+        Span::new(self.position(start), self.position(end))
+    }
+}
+
+/// A `Reducer` that also receives the source span of every node.
+///
+/// Every `Reducer` is a `SpanReducer` that ignores the spans, so the parsers
+/// accept either.
+pub trait SpanReducer<T> {
+    fn node(&self, name: String, children: Option<Vec<T>>, span: Span) -> T;
+    fn id(&self, name: String, span: Span) -> T;
+    fn value(&self, value: Value, span: Span) -> T;
+    fn error(&self, error: &ParseError, span: Span) -> T;
+}
+
+impl<T, R: Reducer<T>> SpanReducer<T> for R {
+    fn node(&self, name: String, children: Option<Vec<T>>, _span: Span) -> T {
+        Reducer::node(self, name, children)
+    }
+    fn id(&self, name: String, _span: Span) -> T {
+        Reducer::id(self, name)
+    }
+    fn value(&self, value: Value, _span: Span) -> T {
+        Reducer::value(self, value)
+    }
+    fn error(&self, error: &ParseError, _span: Span) -> T {
+        Reducer::error(self, error)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_positions() {
+        let source_map = SourceMap::new("ab\nçd\n");
+        assert_eq!(
+            source_map.position(5),
+            Position {
+                offset: 5,
+                line: 2,
+                column: 2
+            }
+        );
+        assert_eq!(source_map.position(6).column, 3);
+        assert_eq!(source_map.position(7), source_map.position(99));
+        assert_eq!(source_map.position(7).line, 3);
+        assert_eq!(source_map.span(3, 6).source("ab\nçd\n"), "çd");
+    }
+}
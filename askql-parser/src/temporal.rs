@@ -0,0 +1,198 @@
+//! ISO-8601 text for `Value::DateTime` and `Value::Duration`.
+//!
+//! Date-times are instants in UTC. They are read from RFC 3339 text with any
+//! offset, from a date and time without one, taken as UTC, or from a bare
+//! date, taken as its midnight in UTC; they are written in RFC 3339 with a `Z`
+//! and a fraction only when there is one. Years past 9999 or before 0, which
+//! RFC 3339 cannot hold, are written with a sign, as `+10000-01-01T00:00:00Z`:
+//!
+//! ```
+//! use askql_parser::{format_date_time, parse_date_time};
+//!
+//! let date_time = parse_date_time("2020-05-01T12:30:00+02:00").unwrap();
+//! assert_eq!(format_date_time(&date_time), "2020-05-01T10:30:00Z");
+//! ```
+//!
+//! Durations are written `PnDTnHnMnS`, leaving out the parts that are zero,
+//! with a leading `-` when negative. Days are 24 hours and weeks 7 days; years
+//! and months, whose length varies, are not accepted.
+
+pub use chrono::{DateTime, TimeDelta, Utc};
+use chrono::{NaiveDate, NaiveDateTime, SecondsFormat};
+use std::fmt::Write;
+
+/// Parses an ISO-8601 date-time, or `None` when `text` is not one.
+pub fn parse_date_time(text: &str) -> Option<DateTime<Utc>> {
+    if let Ok(date_time) = DateTime::parse_from_rfc3339(text) {
+        return Some(date_time.with_timezone(&Utc));
+    }
+    // What `format_date_time` writes for years out of RFC 3339's range.
+    if let Ok(date_time) = DateTime::parse_from_str(text, "%+") {
+        return Some(date_time.with_timezone(&Utc));
+    }
+    if let Ok(date_time) = NaiveDateTime::parse_from_str(text, "%Y-%m-%dT%H:%M:%S%.f") {
+        return Some(date_time.and_utc());
+    }
+    let date = NaiveDate::parse_from_str(text, "%Y-%m-%d").ok()?;
+    Some(date.and_hms_opt(0, 0, 0)?.and_utc())
+}
+
+pub fn format_date_time(date_time: &DateTime<Utc>) -> String {
+    date_time.to_rfc3339_opts(SecondsFormat::AutoSi, true)
+}
+
+/// Parses an ISO-8601 duration such as `P1DT2H` or `-PT0.5S`, or `None` when
+/// `text` is not one or is out of range.
+pub fn parse_duration(text: &str) -> Option<TimeDelta> {
+    let (negative, text) = match text.strip_prefix('-') {
+        Some(text) => (true, text),
+        None => (false, text.strip_prefix('+').unwrap_or(text)),
+    };
+    let mut rest = text.strip_prefix('P')?;
+    let mut duration = TimeDelta::zero();
+    let mut in_time = false;
+    // The position of the next unit allowed, as units must come in order.
+    let mut next = 0;
+    while !rest.is_empty() {
+        if let Some(time) = rest.strip_prefix('T') {
+            if in_time || time.is_empty() {
+                return None;
+            }
+            in_time = true;
+            rest = time;
+            continue;
+        }
+        let len = rest
+            .find(|char: char| !(char.is_ascii_digit() || char == '.'))
+            .unwrap_or(rest.len());
+        let (amount, unit) = rest.split_at(len);
+        let (position, seconds) = match (in_time, unit.chars().next()?) {
+            (false, 'W') => (0, 7 * 86_400),
+            (false, 'D') => (1, 86_400),
+            (true, 'H') => (2, 3_600),
+            (true, 'M') => (3, 60),
+            (true, 'S') => (4, 1),
+            _ => return None,
+        };
+        if position < next {
+            return None;
+        }
+        next = position + 1;
+        let part = if position == 4 {
+            parse_seconds(amount)?
+        } else {
+            TimeDelta::try_seconds(amount.parse::<i64>().ok()?.checked_mul(seconds)?)?
+        };
+        duration = duration.checked_add(&part)?;
+        rest = &unit[1..];
+    }
+    if next == 0 {
+        return None;
+    }
+    Some(if negative { -duration } else { duration })
+}
+
+// Seconds with up to nine digits of fraction.
+fn parse_seconds(text: &str) -> Option<TimeDelta> {
+    let (whole, fraction) = match text.split_once('.') {
+        Some((whole, fraction)) => (whole, Some(fraction)),
+        None => (text, None),
+    };
+    let mut duration = TimeDelta::try_seconds(whole.parse().ok()?)?;
+    if let Some(fraction) = fraction {
+        if fraction.is_empty() || fraction.len() > 9 {
+            return None;
+        }
+        let nanos: i64 = format!("{:0<9}", fraction).parse().ok()?;
+        duration = duration.checked_add(&TimeDelta::nanoseconds(nanos))?;
+    }
+    Some(duration)
+}
+
+pub fn format_duration(duration: &TimeDelta) -> String {
+    let mut text = String::new();
+    if *duration < TimeDelta::zero() {
+        text.push('-');
+    }
+    let duration = duration.abs();
+    let total = duration.num_seconds();
+    let nanos = duration.subsec_nanos();
+    let (days, hours, minutes, seconds) = (
+        total / 86_400,
+        total / 3_600 % 24,
+        total / 60 % 60,
+        total % 60,
+    );
+    text.push('P');
+    if days > 0 {
+        write!(text, "{}D", days).unwrap();
+    }
+    if days == 0 || hours > 0 || minutes > 0 || seconds > 0 || nanos > 0 {
+        text.push('T');
+    }
+    if hours > 0 {
+        write!(text, "{}H", hours).unwrap();
+    }
+    if minutes > 0 {
+        write!(text, "{}M", minutes).unwrap();
+    }
+    if nanos > 0 {
+        let fraction = format!("{:09}", nanos);
+        write!(text, "{}.{}S", seconds, fraction.trim_end_matches('0')).unwrap();
+    } else if seconds > 0 || total == 0 {
+        write!(text, "{}S", seconds).unwrap();
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_date_times() {
+        let format =
+            |text: &str| parse_date_time(text).map(|date_time| format_date_time(&date_time));
+        assert_eq!(
+            format("2020-05-01T12:30:00.250-03:00").as_deref(),
+            Some("2020-05-01T15:30:00.250Z")
+        );
+        assert_eq!(
+            format("2020-05-01T12:30:00").as_deref(),
+            Some("2020-05-01T12:30:00Z")
+        );
+        assert_eq!(
+            format("2020-05-01").as_deref(),
+            Some("2020-05-01T00:00:00Z")
+        );
+        assert_eq!(
+            format("+10000-01-01T00:00:00Z").as_deref(),
+            Some("+10000-01-01T00:00:00Z")
+        );
+        assert_eq!(
+            format("-0001-12-31T23:59:59.5Z").as_deref(),
+            Some("-0001-12-31T23:59:59.500Z")
+        );
+        assert_eq!(format("2020-02-30"), None);
+        assert_eq!(format("yesterday"), None);
+    }
+
+    #[test]
+    fn test_durations() {
+        let duration = |text: &str| parse_duration(text).map(|duration| format_duration(&duration));
+        assert_eq!(duration("P1W").as_deref(), Some("P7D"));
+        assert_eq!(duration("PT90M").as_deref(), Some("PT1H30M"));
+        assert_eq!(duration("P1DT0.5S").as_deref(), Some("P1DT0.5S"));
+        assert_eq!(
+            duration("-PT1.000000001S").as_deref(),
+            Some("-PT1.000000001S")
+        );
+        assert_eq!(duration("PT0S").as_deref(), Some("PT0S"));
+        assert_eq!(duration("P2D").as_deref(), Some("P2D"));
+        for invalid in &[
+            "P", "PT", "P1Y", "P1M", "PT1D", "P1H", "PT1M1H", "P1.5D", "PT1.S", "1D",
+        ] {
+            assert_eq!(parse_duration(invalid), None, "{}", invalid);
+        }
+    }
+}
@@ -0,0 +1,223 @@
+use crate::escape::unescape;
+use crate::number::scan_number;
+use crate::reduce::RESERVED_WORDS;
+use crate::span::{SourceMap, Span};
+use crate::trivia::CommentKind;
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash, Serialize)]
+pub enum TokenKind {
+    Whitespace,
+    Comment(CommentKind),
+    /// A function or variable name.
+    Identifier,
+    /// One of the literals `true`, `false` and `null`.
+    Keyword,
+    Number,
+    /// A quoted string, quotes included.
+    String,
+    /// One of `(`, `)`, `[`, `]`, `{`, `}`, `,` and `:`.
+    Punctuation,
+    /// Text the parser would reject: a malformed number or escape sequence,
+    /// an unterminated string or comment, or a stray character.
+    Invalid,
+}
+
+/// A piece of AskCode source as the parser reads it. `text` borrows from the
+/// source.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Serialize)]
+pub struct Token<'a> {
+    pub kind: TokenKind,
+    pub text: &'a str,
+    pub span: Span,
+}
+
+/// Splits `code` into tokens, whitespace and comments included, so that
+/// their texts joined give `code` back. Tokenizing never fails: anything the
+/// parser would reject comes out as `TokenKind::Invalid`.
+pub fn tokenize(code: &str) -> Tokens<'_> {
+    Tokens {
+        code,
+        index: 0,
+        source_map: SourceMap::new(code),
+    }
+}
+
+/// The iterator returned by `tokenize`.
+pub struct Tokens<'a> {
+    code: &'a str,
+    index: usize,
+    source_map: SourceMap,
+}
+
+impl<'a> Iterator for Tokens<'a> {
+    type Item = Token<'a>;
+
+    fn next(&mut self) -> Option<Token<'a>> {
+        let start = self.index;
+        let rest = &self.code[start..];
+        let bytes = rest.as_bytes();
+        let first = rest.chars().next()?;
+        let (kind, len) = if first.is_whitespace() {
+            let len = rest.len() - rest.trim_start().len();
+            (TokenKind::Whitespace, len)
+        } else if rest.starts_with("//") {
+            let len = rest.find('\n').unwrap_or(rest.len());
+            (TokenKind::Comment(CommentKind::Line), len)
+        } else if let Some(comment) = rest.strip_prefix("/*") {
+            match comment.find("*/") {
+                Some(end) => (TokenKind::Comment(CommentKind::Block), end + 4),
+                None => (TokenKind::Invalid, rest.len()),
+            }
+        } else if first == '\'' || first == '"' {
+            self.string(first as u8)
+        } else if first.is_ascii_digit()
+            || (first == '-' && bytes.get(1).is_some_and(u8::is_ascii_digit))
+        {
+            let digits = if first == '-' { start + 1 } else { start };
+            match scan_number(self.code, digits) {
+                Ok((_, end)) => (TokenKind::Number, end - start),
+                Err(error) => (TokenKind::Invalid, error.end - start),
+            }
+        } else if first == '_' || first.is_ascii_alphabetic() {
+            let len = bytes
+                .iter()
+                .position(|byte| !(byte.is_ascii_alphanumeric() || *byte == b'_'))
+                .unwrap_or(bytes.len());
+            let kind = if RESERVED_WORDS.contains(&&rest[..len]) {
+                TokenKind::Keyword
+            } else {
+                TokenKind::Identifier
+            };
+            (kind, len)
+        } else if "()[]{},:".contains(first) {
+            (TokenKind::Punctuation, 1)
+        } else {
+            (TokenKind::Invalid, first.len_utf8())
+        };
+        self.index += len;
+        Some(Token {
+            kind,
+            text: &self.code[start..self.index],
+            span: self.source_map.span(start, self.index),
+        })
+    }
+}
+
+impl Tokens<'_> {
+    // The kind and length of the string starting at the current index,
+    // checking its escape sequences the way the parser does.
+    fn string(&self, quote: u8) -> (TokenKind, usize) {
+        let bytes = self.code.as_bytes();
+        let mut kind = TokenKind::String;
+        let mut index = self.index + 1;
+        while index < bytes.len() && bytes[index] != quote {
+            if bytes[index] == b'\\' {
+                match unescape(self.code, index) {
+                    Ok((_, end)) => index = end,
+                    Err(error) => {
+                        kind = TokenKind::Invalid;
+                        index = error.end;
+                    }
+                }
+            } else {
+                index += 1;
+            }
+        }
+        if index >= bytes.len() {
+            return (TokenKind::Invalid, bytes.len() - self.index);
+        }
+        (kind, index + 1 - self.index)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    fn kinds(code: &str) -> Vec<(TokenKind, &str)> {
+        tokenize(code)
+            .map(|token| (token.kind, token.text))
+            .collect()
+    }
+
+    #[test]
+    fn test_tokenize() {
+        use TokenKind::*;
+        assert_eq!(
+            kinds("f(x, -1.5e3, 'a\\'b', null) // done"),
+            vec![
+                (Identifier, "f"),
+                (Punctuation, "("),
+                (Identifier, "x"),
+                (Punctuation, ","),
+                (Whitespace, " "),
+                (Number, "-1.5e3"),
+                (Punctuation, ","),
+                (Whitespace, " "),
+                (String, "'a\\'b'"),
+                (Punctuation, ","),
+                (Whitespace, " "),
+                (Keyword, "null"),
+                (Punctuation, ")"),
+                (Whitespace, " "),
+                (Comment(CommentKind::Line), "// done"),
+            ]
+        );
+        assert_eq!(
+            kinds("{k:/* c */\"v\"}"),
+            vec![
+                (Punctuation, "{"),
+                (Identifier, "k"),
+                (Punctuation, ":"),
+                (Comment(CommentKind::Block), "/* c */"),
+                (String, "\"v\""),
+                (Punctuation, "}"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_invalid_tokens() {
+        use TokenKind::*;
+        assert_eq!(
+            kinds("f(1..2, 'a\\q', ;)"),
+            vec![
+                (Identifier, "f"),
+                (Punctuation, "("),
+                (Invalid, "1..2"),
+                (Punctuation, ","),
+                (Whitespace, " "),
+                (Invalid, "'a\\q'"),
+                (Punctuation, ","),
+                (Whitespace, " "),
+                (Invalid, ";"),
+                (Punctuation, ")"),
+            ]
+        );
+        assert_eq!(kinds("'open /* x"), vec![(Invalid, "'open /* x")]);
+        assert_eq!(
+            kinds("x /* open"),
+            vec![(Identifier, "x"), (Whitespace, " "), (Invalid, "/* open")]
+        );
+    }
+
+    #[test]
+    fn test_token_spans() {
+        let code = "ask(\n  'é', x)";
+        let tokens: Vec<Token> = tokenize(code).collect();
+        let x = tokens.iter().find(|token| token.text == "x").unwrap();
+        assert_eq!((x.span.start.line, x.span.start.column), (2, 8));
+        assert_eq!(x.span.source(code), "x");
+        let joined: String = tokens.iter().map(|token| token.text).collect();
+        assert_eq!(joined, code);
+    }
+
+    proptest! {
+        #[test]
+        fn prop_tokens_cover_source(code in any::<String>()) {
+            let joined: String = tokenize(&code).map(|token| token.text).collect();
+            prop_assert_eq!(joined, code);
+        }
+    }
+}
@@ -0,0 +1,19 @@
+use crate::span::Span;
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash, Serialize)]
+pub enum CommentKind {
+    /// `// ...` up to the end of the line.
+    Line,
+    /// `/* ... */`.
+    Block,
+}
+
+/// A comment the parser skipped, kept as trivia for tools such as formatters
+/// that need to put it back. `text` is the comment as written, delimiters
+/// included.
+#[derive(Debug, Clone, Eq, PartialEq, Serialize)]
+pub struct Comment {
+    pub kind: CommentKind,
+    pub text: String,
+    pub span: Span,
+}
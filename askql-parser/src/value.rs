@@ -1,24 +1,52 @@
-use regex::Regex;
+use crate::temporal::{format_date_time, format_duration, DateTime, TimeDelta, Utc};
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+pub use bigdecimal::BigDecimal;
+pub use indexmap::IndexMap;
+pub use num_bigint::BigInt;
+use num_traits::ToPrimitive;
 use serde::{
+    de::{MapAccess, SeqAccess, Visitor},
     ser::{SerializeMap, SerializeSeq, SerializeStruct},
-    Serialize, Serializer,
+    Deserialize, Deserializer, Serialize, Serializer,
 };
-use std::collections::BTreeMap;
+use std::cmp::Ordering;
+use std::convert::TryFrom;
 use std::fmt;
+use std::hash::{Hash, Hasher};
 
 #[derive(Clone, Debug)]
 #[allow(missing_docs)]
 pub enum Value {
     Null,
     Boolean(bool),
-    Int(i32),
-    Float(f32),
+    Int(i64),
+    Float(f64),
+    /// An integer out of the `Int` range.
+    BigInt(BigInt),
+    /// An exact decimal, for literals a `Float` cannot hold.
+    Decimal(BigDecimal),
     Number(Number),
+    /// An instant, in UTC.
+    DateTime(DateTime<Utc>),
+    Duration(TimeDelta),
     String(String),
-    Object(BTreeMap<String, Value>),
+    /// Binary data, written in JSON as a base64 string.
+    Bytes(Vec<u8>),
+    Object(Object),
     List(Vec<Value>),
 }
 
+/// The entries of a `Value::Object`, in the order they were inserted, so
+/// results keep the field order written in a query.
+pub type Object = IndexMap<String, Value>;
+
+/// Values are written as plain JSON, for query results: `BigInt`s and
+/// `Decimal`s as strings, which keeps them exact, `DateTime`s and
+/// `Duration`s as ISO-8601 strings (see `temporal`), `Bytes` as base64
+/// strings, and NaN, the infinities and `Number` literals with no value as
+/// `null`. The AskCode interchange format writes them so they come back as
+/// they were; see `json`.
 impl Serialize for Value {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where
@@ -27,20 +55,18 @@ impl Serialize for Value {
         match self {
             Value::Null => serializer.serialize_none(),
             Value::String(s) => serializer.serialize_str(s),
-            Value::Number(n) => {
-                if n.is_float() {
-                    if let Ok(float) = n.to_float() {
-                        return serializer.serialize_f32(float);
-                    }
-                } else {
-                    if let Ok(int) = n.to_int() {
-                        return serializer.serialize_i32(int);
-                    }
-                }
-                serializer.serialize_i32(0)
-            }
-            Value::Int(i) => serializer.serialize_i32(*i),
-            Value::Float(float) => serializer.serialize_f32(*float),
+            Value::Number(n) => match n.to_value() {
+                Ok(value) => value.serialize(serializer),
+                Err(_) => serializer.serialize_none(),
+            },
+            Value::Int(i) => serializer.serialize_i64(*i),
+            Value::Float(float) => serializer.serialize_f64(*float),
+            // Written as strings, as JSON numbers are read back as doubles.
+            Value::BigInt(int) => serializer.serialize_str(&int.to_string()),
+            Value::Decimal(decimal) => serializer.serialize_str(&decimal.to_string()),
+            Value::DateTime(date_time) => serializer.serialize_str(&format_date_time(date_time)),
+            Value::Duration(duration) => serializer.serialize_str(&format_duration(duration)),
+            Value::Bytes(bytes) => serializer.serialize_str(&BASE64.encode(bytes)),
             Value::Boolean(b) => serializer.serialize_bool(*b),
             Value::List(list) => {
                 let mut seq = serializer.serialize_seq(Some(list.len()))?;
@@ -60,32 +86,339 @@ impl Serialize for Value {
     }
 }
 
+impl<'de> Deserialize<'de> for Value {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_any(ValueVisitor)
+    }
+}
+
+// Also reads the scalars of the tagged form in `json`.
+pub(crate) struct ValueVisitor;
+
+impl<'de> Visitor<'de> for ValueVisitor {
+    type Value = Value;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("any valid JSON value")
+    }
+
+    fn visit_bool<E>(self, value: bool) -> Result<Value, E> {
+        Ok(Value::Boolean(value))
+    }
+
+    fn visit_i64<E>(self, value: i64) -> Result<Value, E> {
+        Ok(Value::Int(value))
+    }
+
+    fn visit_u64<E>(self, value: u64) -> Result<Value, E> {
+        Ok(match i64::try_from(value) {
+            Ok(int) => Value::Int(int),
+            Err(_) => Value::BigInt(value.into()),
+        })
+    }
+
+    fn visit_f64<E>(self, value: f64) -> Result<Value, E> {
+        Ok(Value::Float(value))
+    }
+
+    fn visit_str<E>(self, value: &str) -> Result<Value, E> {
+        Ok(Value::String(value.to_string()))
+    }
+
+    fn visit_string<E>(self, value: String) -> Result<Value, E> {
+        Ok(Value::String(value))
+    }
+
+    fn visit_none<E>(self) -> Result<Value, E> {
+        Ok(Value::Null)
+    }
+
+    fn visit_some<D>(self, deserializer: D) -> Result<Value, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        Deserialize::deserialize(deserializer)
+    }
+
+    fn visit_unit<E>(self) -> Result<Value, E> {
+        Ok(Value::Null)
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> Result<Value, A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        let mut list = Vec::new();
+        while let Some(value) = seq.next_element()? {
+            list.push(value);
+        }
+        Ok(Value::List(list))
+    }
+
+    fn visit_map<A>(self, mut map: A) -> Result<Value, A::Error>
+    where
+        A: MapAccess<'de>,
+    {
+        let mut object = Object::new();
+        while let Some((key, value)) = map.next_entry()? {
+            object.insert(key, value);
+        }
+        Ok(Value::Object(object))
+    }
+}
+
 impl Default for Value {
     fn default() -> Self {
         Value::Null
     }
 }
 
-impl PartialEq for Value {
-    fn eq(&self, other: &Value) -> bool {
+/// Values compare by type first, in the order null, booleans, numbers,
+/// date-times, durations, strings, bytes, lists and objects, then by
+/// content. Lists compare element by element and objects entry by entry, in
+/// key order: insertion order does not matter, so `{a: 1, b: 2}` equals
+/// `{b: 2, a: 1}`.
+///
+/// Numbers compare by value whatever their variant, so `Int(1)`,
+/// `Float(1.0)`, `Decimal(1.00)` and the literal `1` are all equal. A `Float`
+/// with a fraction counts as the shortest decimal that reads back as it, so
+/// `Float(0.1)` equals `Decimal(0.1)`. As in JavaScript's `SameValueZero`,
+/// which sets and `includes` use, `-0.0` equals `0.0` and NaN equals NaN.
+/// Infinities come before and after all finite numbers and NaN after `+inf`;
+/// `Number` literals that do not parse come last, ordered by their text.
+impl Ord for Value {
+    fn cmp(&self, other: &Value) -> Ordering {
         use Value::*;
-        let tuple = (self, other);
-        match tuple {
-            (Null, Null) => true,
-            (Boolean(a), Boolean(b)) => a == b,
-            (Int(a), Int(b)) => a == b,
-            (Float(a), Float(b)) => a == b,
-            (Number(a), Number(b)) => a == b,
-            (String(a), String(b)) => a == b,
-            (Object(a), Object(b)) => a == b,
-            (List(a), List(b)) => a == b,
-            _ => false,
+        match (self, other) {
+            (Int(a), Int(b)) => a.cmp(b),
+            (Boolean(a), Boolean(b)) => a.cmp(b),
+            (DateTime(a), DateTime(b)) => a.cmp(b),
+            (Duration(a), Duration(b)) => a.cmp(b),
+            (String(a), String(b)) => a.cmp(b),
+            (Bytes(a), Bytes(b)) => a.cmp(b),
+            (List(a), List(b)) => a.cmp(b),
+            (Object(a), Object(b)) => sorted(a).cmp(&sorted(b)),
+            _ => match (Numeric::of(self), Numeric::of(other)) {
+                (Some(a), Some(b)) => a.cmp(&b),
+                _ => self.rank().cmp(&other.rank()),
+            },
         }
     }
 }
 
+impl PartialOrd for Value {
+    fn partial_cmp(&self, other: &Value) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for Value {
+    fn eq(&self, other: &Value) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
 impl Eq for Value {}
 
+/// Consistent with equality: numbers that are equal hash the same whatever
+/// their variant.
+impl Hash for Value {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.rank().hash(state);
+        match self {
+            Value::Null => {}
+            Value::Boolean(boolean) => boolean.hash(state),
+            Value::DateTime(date_time) => date_time.hash(state),
+            Value::Duration(duration) => duration.hash(state),
+            Value::String(string) => string.hash(state),
+            Value::Bytes(bytes) => bytes.hash(state),
+            Value::List(list) => list.hash(state),
+            Value::Object(object) => sorted(object).hash(state),
+            number => Numeric::of(number).hash(state),
+        }
+    }
+}
+
+// The entries of an object in key order.
+fn sorted(object: &Object) -> Vec<(&String, &Value)> {
+    let mut entries: Vec<(&String, &Value)> = object.iter().collect();
+    entries.sort_by(|a, b| a.0.cmp(b.0));
+    entries
+}
+
+impl Value {
+    // Where the value's type comes in the order.
+    fn rank(&self) -> u8 {
+        match self {
+            Value::Null => 0,
+            Value::Boolean(_) => 1,
+            Value::Int(_)
+            | Value::Float(_)
+            | Value::BigInt(_)
+            | Value::Decimal(_)
+            | Value::Number(_) => 2,
+            Value::DateTime(_) => 3,
+            Value::Duration(_) => 4,
+            Value::String(_) => 5,
+            Value::Bytes(_) => 6,
+            Value::List(_) => 7,
+            Value::Object(_) => 8,
+        }
+    }
+}
+
+// A number reduced to a form that compares and hashes by value. Integers in
+// the i64 range are always `Int`, other finite floats `Float` and other finite
+// numbers `Exact`, normalized. A `Float` is only turned into a `BigDecimal`
+// to compare it with an `Exact`, so sorting floats does not allocate.
+enum Numeric {
+    NegInfinity,
+    Int(i64),
+    Float(f64),
+    Exact(BigDecimal),
+    Infinity,
+    NaN,
+    Invalid(String),
+}
+
+impl Numeric {
+    fn of(value: &Value) -> Option<Numeric> {
+        Some(match value {
+            Value::Int(int) => Numeric::Int(*int),
+            Value::Float(float) if float.is_nan() => Numeric::NaN,
+            Value::Float(float) if float.is_infinite() && *float > 0.0 => Numeric::Infinity,
+            Value::Float(float) if float.is_infinite() => Numeric::NegInfinity,
+            Value::Float(float) if float.fract() == 0.0 && in_i64_range(*float) => {
+                Numeric::Int(*float as i64)
+            }
+            Value::Float(float) => Numeric::Float(*float),
+            Value::BigInt(int) => Numeric::exact(BigDecimal::from(int.clone())),
+            Value::Decimal(decimal) => Numeric::exact(decimal.clone()),
+            Value::Number(number) => match number.to_value() {
+                Ok(value) => Numeric::of(&value)?,
+                Err(_) => Numeric::Invalid(number.0.clone()),
+            },
+            _ => return None,
+        })
+    }
+
+    // The decimal a finite float counts as. With a precision, floats print
+    // exactly; without one, Display prints the shortest decimal that reads
+    // back as the float.
+    fn float_exact(float: f64) -> Option<BigDecimal> {
+        if float.fract() == 0.0 {
+            format!("{:.0}", float).parse().ok()
+        } else {
+            float.to_string().parse().ok()
+        }
+    }
+
+    fn exact(decimal: BigDecimal) -> Numeric {
+        // Once normalized, the value is an integer when its scale is not
+        // positive, and may fit an i64 only with at most 19 digits. Checking
+        // this first keeps `to_i64` from scaling by an unbounded power of ten.
+        let decimal = decimal.normalized();
+        let (_, scale) = decimal.as_bigint_and_scale();
+        if scale <= 0 && decimal.digits() as i128 - scale as i128 <= 19 {
+            if let Some(int) = decimal.to_i64() {
+                return Numeric::Int(int);
+            }
+        }
+        Numeric::Exact(decimal)
+    }
+
+    fn rank(&self) -> u8 {
+        match self {
+            Numeric::NegInfinity => 0,
+            Numeric::Int(_) | Numeric::Float(_) | Numeric::Exact(_) => 1,
+            Numeric::Infinity => 2,
+            Numeric::NaN => 3,
+            Numeric::Invalid(_) => 4,
+        }
+    }
+}
+
+impl Ord for Numeric {
+    fn cmp(&self, other: &Numeric) -> Ordering {
+        match (self, other) {
+            (Numeric::Int(a), Numeric::Int(b)) => a.cmp(b),
+            // Both finite, and -0.0 is an `Int`.
+            (Numeric::Float(a), Numeric::Float(b)) => a.partial_cmp(b).unwrap(),
+            (Numeric::Int(a), Numeric::Float(b)) => cmp_int_float(*a, *b),
+            (Numeric::Float(a), Numeric::Int(b)) => cmp_int_float(*b, *a).reverse(),
+            (Numeric::Float(a), Numeric::Exact(b)) => match Numeric::float_exact(*a) {
+                Some(a) => a.cmp(b),
+                None => Ordering::Less,
+            },
+            (Numeric::Exact(a), Numeric::Float(b)) => match Numeric::float_exact(*b) {
+                Some(b) => a.cmp(&b),
+                None => Ordering::Greater,
+            },
+            (Numeric::Int(a), Numeric::Exact(b)) => BigDecimal::from(*a).cmp(b),
+            (Numeric::Exact(a), Numeric::Int(b)) => a.cmp(&BigDecimal::from(*b)),
+            (Numeric::Exact(a), Numeric::Exact(b)) => a.cmp(b),
+            (Numeric::Invalid(a), Numeric::Invalid(b)) => a.cmp(b),
+            _ => self.rank().cmp(&other.rank()),
+        }
+    }
+}
+
+impl PartialOrd for Numeric {
+    fn partial_cmp(&self, other: &Numeric) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for Numeric {
+    fn eq(&self, other: &Numeric) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Numeric {}
+
+impl Hash for Numeric {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.rank().hash(state);
+        match self {
+            Numeric::Int(int) => int.hash(state),
+            // An `Exact` by the float nearest it, which is the float itself
+            // when the two are equal.
+            Numeric::Float(float) => float.to_bits().hash(state),
+            Numeric::Exact(decimal) => {
+                let float: f64 = decimal.to_string().parse().unwrap_or(f64::NAN);
+                float.to_bits().hash(state)
+            }
+            Numeric::Invalid(text) => text.hash(state),
+            Numeric::NegInfinity | Numeric::Infinity | Numeric::NaN => {}
+        }
+    }
+}
+
+fn in_i64_range(float: f64) -> bool {
+    (-9223372036854775808.0..9223372036854775808.0).contains(&float)
+}
+
+// Compares an integer with a finite float that is not one in the i64 range.
+fn cmp_int_float(int: i64, float: f64) -> Ordering {
+    if !in_i64_range(float) {
+        return if float > 0.0 {
+            Ordering::Less
+        } else {
+            Ordering::Greater
+        };
+    }
+    // The float has a fraction, so it lies strictly between two integers.
+    match int.cmp(&(float.trunc() as i64)) {
+        Ordering::Equal if float > 0.0 => Ordering::Less,
+        Ordering::Equal => Ordering::Greater,
+        ordering => ordering,
+    }
+}
+
 impl Value {
     pub fn null() -> Self {
         Value::Null
@@ -93,10 +426,10 @@ impl Value {
     pub fn boolean(val: bool) -> Self {
         Value::Boolean(val)
     }
-    pub fn int(val: i32) -> Self {
+    pub fn int(val: i64) -> Self {
         Value::Int(val)
     }
-    pub fn float(val: f32) -> Self {
+    pub fn float(val: f64) -> Self {
         Value::Float(val)
     }
     pub fn string(val: String) -> Self {
@@ -127,47 +460,623 @@ impl Value {
     }
 }
 
+impl From<bool> for Value {
+    fn from(value: bool) -> Self {
+        Value::Boolean(value)
+    }
+}
+
+impl From<i32> for Value {
+    fn from(value: i32) -> Self {
+        Value::Int(value.into())
+    }
+}
+
+impl From<i64> for Value {
+    fn from(value: i64) -> Self {
+        Value::Int(value)
+    }
+}
+
+impl From<f32> for Value {
+    fn from(value: f32) -> Self {
+        Value::Float(value.into())
+    }
+}
+
+impl From<f64> for Value {
+    fn from(value: f64) -> Self {
+        Value::Float(value)
+    }
+}
+
+impl From<BigInt> for Value {
+    fn from(value: BigInt) -> Self {
+        Value::BigInt(value)
+    }
+}
+
+impl From<BigDecimal> for Value {
+    fn from(value: BigDecimal) -> Self {
+        Value::Decimal(value)
+    }
+}
+
+impl From<DateTime<Utc>> for Value {
+    fn from(value: DateTime<Utc>) -> Self {
+        Value::DateTime(value)
+    }
+}
+
+impl From<TimeDelta> for Value {
+    fn from(value: TimeDelta) -> Self {
+        Value::Duration(value)
+    }
+}
+
+impl From<String> for Value {
+    fn from(value: String) -> Self {
+        Value::String(value)
+    }
+}
+
+impl From<&str> for Value {
+    fn from(value: &str) -> Self {
+        Value::String(value.to_string())
+    }
+}
+
+impl From<Vec<Value>> for Value {
+    fn from(value: Vec<Value>) -> Self {
+        Value::List(value)
+    }
+}
+
+impl From<Object> for Value {
+    fn from(value: Object) -> Self {
+        Value::Object(value)
+    }
+}
+
 impl fmt::Display for Value {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(f, "{}", serde_json::to_string(self).unwrap())
     }
 }
 
-#[derive(Clone, Debug, Eq, PartialEq, Serialize)]
+/// A numeric literal as written in the source, kept as decimal text so that no
+/// precision is lost until it is converted.
+///
+/// The text is `[-+]digits[.digits][e[-+]digits]`; anything else is not a
+/// number and fails every conversion. Conversions go through `TryFrom`:
+/// integer types accept any literal whose value is a whole number in range
+/// (`1e3`, `2.0`), float types any literal whose value does not round to an
+/// infinity.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
 pub struct Number(pub String);
 
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
 pub enum NumberConvertError {
+    /// The text is not a number literal.
     NotANumber,
+    /// The value has a fractional part but an integer type was asked for.
+    NotAnInteger,
+    /// The value is out of the target type's range.
+    Overflow,
+}
+
+impl fmt::Display for NumberConvertError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            NumberConvertError::NotANumber => "not a number",
+            NumberConvertError::NotAnInteger => "number is not an integer",
+            NumberConvertError::Overflow => "number is out of range",
+        })
+    }
+}
+
+impl std::error::Error for NumberConvertError {}
+
+/// The largest exponent, either way, a literal may have when `to_value` makes
+/// a `Decimal` of it; past it, the decimal would be too large to work with.
+pub const MAX_DECIMAL_EXPONENT: i64 = 4096;
+
+// A number literal split into its parts, borrowed from its text.
+struct Decimal<'a> {
+    negative: bool,
+    int: &'a str,
+    fraction: Option<&'a str>,
+    exponent: Option<i64>,
+}
+
+impl<'a> Decimal<'a> {
+    fn parse(text: &'a str) -> Option<Self> {
+        let (negative, rest) = match text.as_bytes().first() {
+            Some(b'-') => (true, &text[1..]),
+            Some(b'+') => (false, &text[1..]),
+            _ => (false, text),
+        };
+        let (mantissa, exponent) = match rest.find(['e', 'E']) {
+            Some(index) => (&rest[..index], Some(&rest[index + 1..])),
+            None => (rest, None),
+        };
+        let (int, fraction) = match mantissa.find('.') {
+            Some(index) => (&mantissa[..index], Some(&mantissa[index + 1..])),
+            None => (mantissa, None),
+        };
+        let is_digits = |part: &str| !part.is_empty() && part.bytes().all(|b| b.is_ascii_digit());
+        if !is_digits(int) || !fraction.is_none_or(is_digits) {
+            return None;
+        }
+        let exponent = match exponent {
+            None => None,
+            Some(exponent) => {
+                let digits = exponent.strip_prefix(['-', '+']).unwrap_or(exponent);
+                if !is_digits(digits) {
+                    return None;
+                }
+                // Exponents too large for an i64 are clamped; either way the
+                // value overflows or rounds to zero.
+                Some(exponent.parse().unwrap_or(if exponent.starts_with('-') {
+                    i64::MIN / 2
+                } else {
+                    i64::MAX / 2
+                }))
+            }
+        };
+        Some(Decimal {
+            negative,
+            int,
+            fraction,
+            exponent,
+        })
+    }
+
+    // The value as a sign and a magnitude, if it is a whole number that fits
+    // in a u128.
+    fn integer(&self) -> Result<(bool, u128), NumberConvertError> {
+        let fraction = self.fraction.unwrap_or("");
+        let digits = self.int.bytes().chain(fraction.bytes());
+        // Shift the point right by the exponent: the first `point` digits are
+        // the integer part, the rest must all be zero.
+        let point = self.int.len() as i64 + self.exponent.unwrap_or(0);
+        let mut magnitude: u128 = 0;
+        for (index, digit) in digits.enumerate() {
+            let digit = u128::from(digit - b'0');
+            if index as i64 >= point {
+                if digit != 0 {
+                    return Err(NumberConvertError::NotAnInteger);
+                }
+                continue;
+            }
+            magnitude = magnitude
+                .checked_mul(10)
+                .and_then(|magnitude| magnitude.checked_add(digit))
+                .ok_or(NumberConvertError::Overflow)?;
+        }
+        // Trailing zeros the exponent adds past the written digits.
+        let written = (self.int.len() + fraction.len()) as i64;
+        if magnitude != 0 && point > written {
+            for _ in written..point {
+                magnitude = magnitude
+                    .checked_mul(10)
+                    .ok_or(NumberConvertError::Overflow)?;
+            }
+        }
+        Ok((self.negative, magnitude))
+    }
 }
 
 impl Number {
+    /// Whether the literal is written as an integer, without a fraction or an
+    /// exponent.
     pub fn is_int(&self) -> bool {
-        !self.is_float()
+        match Decimal::parse(&self.0) {
+            Some(decimal) => decimal.fraction.is_none() && decimal.exponent.is_none(),
+            None => false,
+        }
     }
+    /// Whether the literal is written with a fraction or an exponent.
     pub fn is_float(&self) -> bool {
-        lazy_static! {
-            static ref IS_FLOAT_REGEX: Regex = Regex::new(r#"^[-+]?\d*\.\d*$"#).unwrap();
+        match Decimal::parse(&self.0) {
+            Some(decimal) => decimal.fraction.is_some() || decimal.exponent.is_some(),
+            None => false,
         }
-        IS_FLOAT_REGEX.is_match(&self.0)
     }
-    pub fn to_int(&self) -> Result<i32, NumberConvertError> {
-        use NumberConvertError::*;
-        self.0.parse().map_err(|_| NotANumber)
+    pub fn to_int(&self) -> Result<i64, NumberConvertError> {
+        i64::try_from(self)
     }
-    pub fn to_float(&self) -> Result<f32, NumberConvertError> {
-        use NumberConvertError::*;
-        self.0.parse().map_err(|_| NotANumber)
+    pub fn to_float(&self) -> Result<f64, NumberConvertError> {
+        f64::try_from(self)
+    }
+    /// The value the literal evaluates to. Integers are an `Int`, or a
+    /// `BigInt` out of its range. Other literals are a `Float` when it holds
+    /// them exactly as written, that is when the float prints back as the same
+    /// decimal, and a `Decimal` otherwise. A literal whose exponent is beyond
+    /// `MAX_DECIMAL_EXPONENT` is out of range.
+    pub fn to_value(&self) -> Result<Value, NumberConvertError> {
+        let decimal = Decimal::parse(&self.0).ok_or(NumberConvertError::NotANumber)?;
+        if decimal.exponent.unwrap_or(0).abs() > MAX_DECIMAL_EXPONENT {
+            return Err(NumberConvertError::Overflow);
+        }
+        if decimal.fraction.is_none() && decimal.exponent.is_none() {
+            return Ok(match self.to_int() {
+                Ok(int) => Value::Int(int),
+                Err(_) => {
+                    Value::BigInt(self.0.parse().map_err(|_| NumberConvertError::NotANumber)?)
+                }
+            });
+        }
+        let exact: BigDecimal = self.0.parse().map_err(|_| NumberConvertError::NotANumber)?;
+        if let Ok(float) = self.to_float() {
+            if float.to_string().parse::<BigDecimal>().ok() == Some(exact.clone()) {
+                return Ok(Value::Float(float));
+            }
+        }
+        Ok(Value::Decimal(exact))
     }
 }
 
-impl From<Number> for i32 {
-    fn from(value: Number) -> i32 {
-        todo!()
-    }
+macro_rules! impl_try_from_number_for_int {
+    ($($int:ty),*) => {$(
+        impl TryFrom<&Number> for $int {
+            type Error = NumberConvertError;
+            fn try_from(number: &Number) -> Result<Self, Self::Error> {
+                let decimal = Decimal::parse(&number.0).ok_or(NumberConvertError::NotANumber)?;
+                let (negative, magnitude) = decimal.integer()?;
+                if negative && magnitude != 0 {
+                    let value = if magnitude == 1 << 127 {
+                        i128::MIN
+                    } else {
+                        -i128::try_from(magnitude).map_err(|_| NumberConvertError::Overflow)?
+                    };
+                    <$int>::try_from(value).map_err(|_| NumberConvertError::Overflow)
+                } else {
+                    <$int>::try_from(magnitude).map_err(|_| NumberConvertError::Overflow)
+                }
+            }
+        }
+
+        impl TryFrom<Number> for $int {
+            type Error = NumberConvertError;
+            fn try_from(number: Number) -> Result<Self, Self::Error> {
+                <$int>::try_from(&number)
+            }
+        }
+    )*};
 }
 
-impl From<Number> for f32 {
-    fn from(value: Number) -> f32 {
-        todo!()
+impl_try_from_number_for_int!(i8, i16, i32, i64, i128, isize, u8, u16, u32, u64, u128, usize);
+
+macro_rules! impl_try_from_number_for_float {
+    ($($float:ty),*) => {$(
+        impl TryFrom<&Number> for $float {
+            type Error = NumberConvertError;
+            fn try_from(number: &Number) -> Result<Self, Self::Error> {
+                Decimal::parse(&number.0).ok_or(NumberConvertError::NotANumber)?;
+                // The standard parser rounds correctly; a literal it rounds
+                // to an infinity is out of range.
+                let float: $float = number.0.parse().map_err(|_| NumberConvertError::NotANumber)?;
+                if float.is_infinite() {
+                    return Err(NumberConvertError::Overflow);
+                }
+                Ok(float)
+            }
+        }
+
+        impl TryFrom<Number> for $float {
+            type Error = NumberConvertError;
+            fn try_from(number: Number) -> Result<Self, Self::Error> {
+                <$float>::try_from(&number)
+            }
+        }
+    )*};
+}
+
+impl_try_from_number_for_float!(f32, f64);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::temporal::{parse_date_time, parse_duration};
+    use proptest::prelude::*;
+
+    fn number(text: &str) -> Number {
+        Number(text.to_string())
+    }
+
+    #[test]
+    fn test_number_classification() {
+        assert!(number("-12").is_int());
+        assert!(number("1e3").is_float());
+        assert!(number("1.5E-3").is_float());
+        for text in &[
+            "", "-", "1.", ".5", "1e", "1e+", "0x10", "inf", "NaN", "1_000",
+        ] {
+            assert!(
+                !number(text).is_int() && !number(text).is_float(),
+                "{}",
+                text
+            );
+            assert_eq!(
+                i32::try_from(number(text)),
+                Err(NumberConvertError::NotANumber)
+            );
+            assert_eq!(
+                f64::try_from(number(text)),
+                Err(NumberConvertError::NotANumber)
+            );
+        }
+    }
+
+    #[test]
+    fn test_number_to_integers() {
+        assert_eq!(i32::try_from(number("1e3")), Ok(1000));
+        assert_eq!(u8::try_from(number("25.5e1")), Ok(255));
+        assert_eq!(i8::try_from(number("-128.000")), Ok(-128));
+        assert_eq!(u32::try_from(number("-0")), Ok(0));
+        assert_eq!(i64::try_from(number("0e999999999999999999999")), Ok(0));
+        assert_eq!(i64::try_from(number("-9223372036854775808")), Ok(i64::MIN));
+        assert_eq!(
+            u128::try_from(number("340282366920938463463374607431768211455")),
+            Ok(u128::MAX)
+        );
+        assert_eq!(
+            i128::try_from(number("-170141183460469231731687303715884105728")),
+            Ok(i128::MIN)
+        );
+        assert_eq!(
+            u8::try_from(number("256")),
+            Err(NumberConvertError::Overflow)
+        );
+        assert_eq!(
+            u8::try_from(number("-1")),
+            Err(NumberConvertError::Overflow)
+        );
+        assert_eq!(
+            i64::try_from(number("9223372036854775808")),
+            Err(NumberConvertError::Overflow)
+        );
+        assert_eq!(
+            u128::try_from(number("1e39")),
+            Err(NumberConvertError::Overflow)
+        );
+        assert_eq!(
+            i32::try_from(number("1.5")),
+            Err(NumberConvertError::NotAnInteger)
+        );
+        assert_eq!(
+            i32::try_from(number("1e-3")),
+            Err(NumberConvertError::NotAnInteger)
+        );
+    }
+
+    #[test]
+    fn test_number_to_floats() {
+        assert_eq!(f64::try_from(number("0.1")), Ok(0.1));
+        assert_eq!(f32::try_from(number("-1.5e3")), Ok(-1500.0));
+        assert_eq!(f64::try_from(number("1e-400")), Ok(0.0));
+        assert_eq!(
+            f32::try_from(number("1e39")),
+            Err(NumberConvertError::Overflow)
+        );
+        assert_eq!(
+            f64::try_from(number("1e309")),
+            Err(NumberConvertError::Overflow)
+        );
+    }
+
+    #[test]
+    fn test_number_to_value() {
+        assert_eq!(number("42").to_value(), Ok(Value::Int(42)));
+        assert_eq!(number("1e3").to_value(), Ok(Value::Float(1000.0)));
+        assert_eq!(number("0.1").to_value(), Ok(Value::Float(0.1)));
+        assert_eq!(
+            number("9223372036854775808").to_value(),
+            Ok(Value::BigInt("9223372036854775808".parse().unwrap()))
+        );
+        assert_eq!(
+            number("0.10000000000000000001").to_value(),
+            Ok(Value::Decimal("0.10000000000000000001".parse().unwrap()))
+        );
+        assert_eq!(
+            number("1e309").to_value(),
+            Ok(Value::Decimal("1e309".parse().unwrap()))
+        );
+        assert_eq!(
+            number("-1.5e4096").to_value(),
+            Ok(Value::Decimal("-1.5e4096".parse().unwrap()))
+        );
+        for text in &["1e99999999", "1e-4097", "0e99999999999999999999"] {
+            assert_eq!(
+                number(text).to_value(),
+                Err(NumberConvertError::Overflow),
+                "{}",
+                text
+            );
+        }
+        assert_eq!(number("1x").to_value(), Err(NumberConvertError::NotANumber));
+    }
+
+    #[test]
+    fn test_value_equality() {
+        let decimal = |text: &str| Value::Decimal(text.parse().unwrap());
+        assert_eq!(Value::Int(1), Value::Float(1.0));
+        assert_eq!(Value::Float(0.1), decimal("0.1000"));
+        assert_eq!(Value::Float(-0.0), Value::Int(0));
+        assert_eq!(Value::Float(f64::NAN), Value::Float(f64::NAN));
+        assert_eq!(Value::Number(number("1e3")), Value::Int(1000));
+        assert_eq!(
+            Value::BigInt("9223372036854775808".parse().unwrap()),
+            Value::Float(9223372036854775808.0)
+        );
+        assert_ne!(
+            Value::Int(9007199254740993),
+            Value::Float(9007199254740992.0)
+        );
+        assert_ne!(
+            Value::Float(0.1),
+            decimal("0.1000000000000000055511151231257827")
+        );
+        assert_ne!(Value::Int(1), Value::String("1".to_string()));
+        assert_ne!(Value::Null, Value::Boolean(false));
+    }
+
+    #[test]
+    fn test_value_extreme_scales() {
+        let decimal = |text: &str| Value::Decimal(text.parse().unwrap());
+        assert_eq!(decimal("1e-999999999999"), decimal("10e-1000000000000"));
+        assert_ne!(decimal("1e999999999999"), Value::Int(1));
+        assert!(decimal("-1e999999999999") < Value::Int(i64::MIN));
+        assert_eq!(decimal("92233720368547758.07e2"), Value::Int(i64::MAX));
+        assert_eq!(
+            decimal("9223372036854775808e0"),
+            Value::BigInt("9223372036854775808".parse().unwrap())
+        );
+        let set: std::collections::HashSet<Value> =
+            vec![decimal("5e999999999999"), decimal("0.5e1000000000000")]
+                .into_iter()
+                .collect();
+        assert_eq!(set.len(), 1);
+    }
+
+    #[test]
+    fn test_value_order() {
+        let mut values = vec![
+            Value::Object(Object::new()),
+            Value::List(vec![Value::Int(1)]),
+            Value::Bytes(vec![0]),
+            Value::Bytes(vec![]),
+            Value::String("a".to_string()),
+            Value::Duration(TimeDelta::seconds(-1)),
+            Value::DateTime(DateTime::UNIX_EPOCH),
+            Value::Number(number("x")),
+            Value::Float(f64::NAN),
+            Value::Float(f64::INFINITY),
+            Value::Decimal("2.5".parse().unwrap()),
+            Value::Int(2),
+            Value::BigInt("-9223372036854775809".parse().unwrap()),
+            Value::Float(f64::NEG_INFINITY),
+            Value::Boolean(true),
+            Value::Boolean(false),
+            Value::Null,
+        ];
+        let expected: Vec<Value> = values.iter().rev().cloned().collect();
+        values.sort();
+        assert_eq!(format!("{:?}", values), format!("{:?}", expected));
+        assert!(Value::List(vec![Value::Int(1)]) < Value::List(vec![Value::Int(1), Value::Null]));
+    }
+
+    #[test]
+    fn test_value_hash() {
+        use std::collections::hash_map::DefaultHasher;
+        use std::collections::HashSet;
+        let hash = |value: Value| {
+            let mut hasher = DefaultHasher::new();
+            value.hash(&mut hasher);
+            hasher.finish()
+        };
+        assert_eq!(hash(Value::Int(3)), hash(Value::Float(3.0)));
+        assert_eq!(
+            hash(Value::Float(0.25)),
+            hash(Value::Decimal("0.2500".parse().unwrap()))
+        );
+        assert_eq!(
+            hash(Value::Float(0.1)),
+            hash(Value::Decimal("0.1".parse().unwrap()))
+        );
+        assert_eq!(
+            hash(Value::Float(1e20)),
+            hash(Value::BigInt("100000000000000000000".parse().unwrap()))
+        );
+        assert_eq!(
+            hash(Value::BigInt("9223372036854775808".parse().unwrap())),
+            hash(Value::Float(9223372036854775808.0))
+        );
+        let set: HashSet<Value> = vec![
+            Value::Int(1),
+            Value::Float(1.0),
+            Value::Number(number("1")),
+            Value::Float(f64::NAN),
+            Value::Float(f64::NAN),
+        ]
+        .into_iter()
+        .collect();
+        assert_eq!(set.len(), 2);
+    }
+
+    #[test]
+    fn test_float_order() {
+        assert!(Value::Int(2) < Value::Float(2.5));
+        assert!(Value::Int(-2) > Value::Float(-2.5));
+        assert!(Value::Int(0) > Value::Float(-0.5));
+        assert!(Value::Int(i64::MAX) < Value::Float(9223372036854775808.0));
+        assert!(Value::Int(i64::MIN) > Value::Float(-1e300));
+        assert!(Value::Float(0.1) < Value::Float(0.2));
+        assert!(Value::Float(1e300) < Value::Float(f64::INFINITY));
+    }
+
+    // The floats' fast paths agree with comparing their decimals.
+    fn exact(float: f64) -> Value {
+        Value::Decimal(Numeric::float_exact(float).unwrap())
+    }
+
+    proptest! {
+        #[test]
+        fn prop_floats_compare_as_decimals(a: f64, b: f64, int: i64, fraction in 0.0..1.0f64) {
+            prop_assume!(a.is_finite() && b.is_finite());
+            let near = int as f64 + fraction;
+            prop_assert_eq!(Value::Float(a).cmp(&Value::Float(b)), exact(a).cmp(&exact(b)));
+            for float in &[b, near] {
+                prop_assert_eq!(
+                    Value::Int(int).cmp(&Value::Float(*float)),
+                    Value::Int(int).cmp(&exact(*float))
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_serialize_numbers() {
+        let value = Value::List(vec![
+            Value::BigInt("-9223372036854775809".parse().unwrap()),
+            Value::Decimal("0.10000000000000000001".parse().unwrap()),
+            Value::Float(f64::NAN),
+            Value::Number(Number("1x".to_string())),
+        ]);
+        assert_eq!(
+            value.to_string(),
+            r#"["-9223372036854775809","0.10000000000000000001",null,null]"#
+        );
+    }
+
+    #[test]
+    fn test_serialize_temporal() {
+        let value = Value::List(vec![
+            Value::DateTime(parse_date_time("2020-05-01T10:30:00.5Z").unwrap()),
+            Value::Duration(parse_duration("-P1DT2H").unwrap()),
+        ]);
+        assert_eq!(
+            value.to_string(),
+            r#"["2020-05-01T10:30:00.500Z","-P1DT2H"]"#
+        );
+    }
+
+    #[test]
+    fn test_serialize_bytes() {
+        assert_eq!(Value::Bytes(b"hi\xff".to_vec()).to_string(), r#""aGn/""#);
+        assert_eq!(Value::Bytes(vec![]).to_string(), r#""""#);
+    }
+
+    #[test]
+    fn test_object_order() {
+        let object: Value = serde_json::from_str(r#"{"b": 1, "a": {"d": 2, "c": 3}}"#).unwrap();
+        assert_eq!(
+            serde_json::to_string(&object).unwrap(),
+            r#"{"b":1,"a":{"d":2,"c":3}}"#
+        );
+        let reordered: Value = serde_json::from_str(r#"{"a": {"c": 3, "d": 2}, "b": 1}"#).unwrap();
+        assert_eq!(object, reordered);
+        assert_eq!(object.cmp(&reordered), Ordering::Equal);
     }
 }
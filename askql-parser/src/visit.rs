@@ -0,0 +1,188 @@
+//! Traversal of AskCode trees.
+//!
+//! `Visitor` walks a tree by reference, `VisitorMut` edits it in place and
+//! `Fold` rebuilds it by value. Every method defaults to visiting the
+//! children through the matching `walk_*` or `fold_*` function, so an
+//! implementation overrides only the nodes it cares about and calls the
+//! function itself to keep descending.
+
+use crate::askcode::{AskCode, AskCodeOrValue};
+use crate::value::Value;
+
+pub trait Visitor {
+    fn visit_ask_code_or_value(&mut self, code: &AskCodeOrValue) {
+        walk_ask_code_or_value(self, code)
+    }
+
+    fn visit_ask_code(&mut self, code: &AskCode) {
+        walk_ask_code(self, code)
+    }
+
+    fn visit_value(&mut self, _value: &Value) {}
+}
+
+pub fn walk_ask_code_or_value<V: Visitor + ?Sized>(visitor: &mut V, code: &AskCodeOrValue) {
+    match code {
+        AskCodeOrValue::AskCode(code) => visitor.visit_ask_code(code),
+        AskCodeOrValue::Value(value) => visitor.visit_value(value),
+    }
+}
+
+pub fn walk_ask_code<V: Visitor + ?Sized>(visitor: &mut V, code: &AskCode) {
+    for param in code.params.iter().flatten() {
+        visitor.visit_ask_code_or_value(param);
+    }
+}
+
+pub trait VisitorMut {
+    fn visit_ask_code_or_value_mut(&mut self, code: &mut AskCodeOrValue) {
+        walk_ask_code_or_value_mut(self, code)
+    }
+
+    fn visit_ask_code_mut(&mut self, code: &mut AskCode) {
+        walk_ask_code_mut(self, code)
+    }
+
+    fn visit_value_mut(&mut self, _value: &mut Value) {}
+}
+
+pub fn walk_ask_code_or_value_mut<V: VisitorMut + ?Sized>(
+    visitor: &mut V,
+    code: &mut AskCodeOrValue,
+) {
+    match code {
+        AskCodeOrValue::AskCode(code) => visitor.visit_ask_code_mut(code),
+        AskCodeOrValue::Value(value) => visitor.visit_value_mut(value),
+    }
+}
+
+pub fn walk_ask_code_mut<V: VisitorMut + ?Sized>(visitor: &mut V, code: &mut AskCode) {
+    for param in code.params.iter_mut().flatten() {
+        visitor.visit_ask_code_or_value_mut(param);
+    }
+}
+
+/// Rebuilds a tree. `fold_ask_code` may replace a node with a value, e.g.
+/// the result of evaluating it at compile time.
+pub trait Fold {
+    fn fold_ask_code_or_value(&mut self, code: AskCodeOrValue) -> AskCodeOrValue {
+        fold_ask_code_or_value(self, code)
+    }
+
+    fn fold_ask_code(&mut self, code: AskCode) -> AskCodeOrValue {
+        fold_ask_code(self, code)
+    }
+
+    fn fold_value(&mut self, value: Value) -> Value {
+        value
+    }
+}
+
+pub fn fold_ask_code_or_value<F: Fold + ?Sized>(
+    folder: &mut F,
+    code: AskCodeOrValue,
+) -> AskCodeOrValue {
+    match code {
+        AskCodeOrValue::AskCode(code) => folder.fold_ask_code(code),
+        AskCodeOrValue::Value(value) => AskCodeOrValue::Value(folder.fold_value(value)),
+    }
+}
+
+/// Folds the params of `code`, keeping the node itself.
+pub fn fold_ask_code<F: Fold + ?Sized>(folder: &mut F, code: AskCode) -> AskCodeOrValue {
+    let AskCode { name, params } = code;
+    let params = params.map(|params| {
+        params
+            .into_iter()
+            .map(|param| folder.fold_ask_code_or_value(param))
+            .collect()
+    });
+    AskCodeOrValue::AskCode(AskCode::new(name, params))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse::parse;
+    use crate::value::Number;
+
+    fn parsed(code: &str) -> AskCodeOrValue {
+        parse(code.to_string(), false).unwrap()
+    }
+
+    #[derive(Default)]
+    struct Identifiers(Vec<String>);
+
+    impl Visitor for Identifiers {
+        fn visit_ask_code(&mut self, code: &AskCode) {
+            if code.params.is_none() {
+                self.0.push(code.name.clone());
+            }
+            walk_ask_code(self, code);
+        }
+    }
+
+    #[test]
+    fn test_visitor() {
+        let mut identifiers = Identifiers::default();
+        identifiers.visit_ask_code_or_value(&parsed("max(a, f(b, 1), [c])"));
+        assert_eq!(identifiers.0, vec!["a", "b", "c"]);
+    }
+
+    struct Rename<'a>(&'a str, &'a str);
+
+    impl VisitorMut for Rename<'_> {
+        fn visit_ask_code_mut(&mut self, code: &mut AskCode) {
+            if code.name == self.0 {
+                code.name = self.1.to_string();
+            }
+            walk_ask_code_mut(self, code);
+        }
+    }
+
+    #[test]
+    fn test_visitor_mut() {
+        let mut code = parsed("max(min(1, min), f(min(2)))");
+        Rename("min", "least").visit_ask_code_or_value_mut(&mut code);
+        assert_eq!(code, parsed("max(least(1, least), f(least(2)))"));
+    }
+
+    // Adds up `call(get('+'), ...)` when every argument is a number literal.
+    struct ConstantSums;
+
+    impl Fold for ConstantSums {
+        fn fold_ask_code(&mut self, code: AskCode) -> AskCodeOrValue {
+            let folded = fold_ask_code(self, code);
+            let params = match &folded {
+                AskCodeOrValue::AskCode(code) if code.name == "call" => code.params.as_deref(),
+                _ => None,
+            };
+            if let Some([function, args @ ..]) = params {
+                if *function == parsed("get('+')") {
+                    let sum: Option<i32> = args
+                        .iter()
+                        .map(|arg| match arg {
+                            AskCodeOrValue::Value(Value::Number(Number(number))) => {
+                                number.parse::<i32>().ok()
+                            }
+                            _ => None,
+                        })
+                        .sum();
+                    if let Some(sum) = sum {
+                        return AskCodeOrValue::Value(Value::number(sum.to_string()));
+                    }
+                }
+            }
+            folded
+        }
+    }
+
+    #[test]
+    fn test_fold() {
+        let code = parsed("ask(call(get('+'), 1, call(get('+'), 2, 3)), call(get('+'), x, 1))");
+        assert_eq!(
+            ConstantSums.fold_ask_code_or_value(code),
+            parsed("ask(6, call(get('+'), x, 1))")
+        );
+    }
+}
@@ -5,7 +5,8 @@ extern crate log;
 use actix_cors::Cors;
 use actix_web::{middleware, web, App, Error, HttpResponse, HttpServer};
 
-use askql_parser::{AskCodeOrValue, Value};
+use askql_macros::IntoValue;
+use askql_parser::{AskCodeOrValue, IntoValue, Value};
 use askql_vm::resources::*;
 use askql_vm::run::{AskVm, RunOptions};
 
@@ -14,6 +15,15 @@ struct Payload {
     code: String,
 }
 
+#[derive(IntoValue)]
+struct Friend {
+    id: i64,
+    #[askql(rename = "firstName")]
+    first_name: String,
+    #[askql(rename = "lastName")]
+    last_name: String,
+}
+
 fn vm() -> AskVm {
     let mut values = std::collections::HashMap::new();
     values.insert(
@@ -24,55 +34,36 @@ fn vm() -> AskVm {
         "lastName".to_string(),
         AskCodeOrValue::new_value(Value::String("SecondName".to_string())),
     );
-    let mut friend0 = std::collections::BTreeMap::new();
-    let mut friend1 = std::collections::BTreeMap::new();
-    let mut friend2 = std::collections::BTreeMap::new();
-    friend0.insert("id".to_string(), Value::Int(1));
-    friend0.insert(
-        "firstName".to_string(),
-        Value::String("Friend 1".to_string()),
-    );
-    friend0.insert(
-        "lastName".to_string(),
-        Value::String("1".to_string())
-    );
-    friend1.insert("id".to_string(), Value::Int(2));
-    friend1.insert(
-        "firstName".to_string(),
-        Value::String("Friend 2".to_string()),
-    );
-    friend1.insert(
-        "lastName".to_string(),
-        Value::String("2".to_string())
-    );
-    friend2.insert("id".to_string(), Value::Int(3));
-    friend2.insert(
-        "firstName".to_string(),
-        Value::String("Friend 3".to_string()),
-    );
-    friend2.insert(
-        "lastName".to_string(),
-        Value::String("3".to_string())
-    );
-    let friends = vec![
-        Value::Object(friend0),
-        Value::Object(friend1),
-        Value::Object(friend2),
-    ];
+    let friends: Vec<Friend> = (1..=3)
+        .map(|id| Friend {
+            id,
+            first_name: format!("Friend {}", id),
+            last_name: id.to_string(),
+        })
+        .collect();
     values.insert(
         "friends".to_string(),
-        AskCodeOrValue::new_value(Value::List(friends)),
+        AskCodeOrValue::new_value(friends.into_value()),
     );
     let mut run_options = RunOptions::new(vec![], values);
     run_options.register(AskResource);
     run_options.register(CallResource);
     run_options.register(GetResource);
+    run_options.register(LetResource);
     run_options.register(SumResource);
     run_options.register(MinusResource);
     run_options.register(TimesResource);
     run_options.register(ConcatResource);
     run_options.register(MaxResource);
+    run_options.register(EqualsResource);
+    run_options.register(NotEqualsResource);
+    run_options.register(DivideResource);
+    run_options.register(RemainderResource);
+    run_options.register(AndResource);
+    run_options.register(OrResource);
+    run_options.register(NotResource);
     run_options.register(ListResource);
+    run_options.register(ObjectResource);
     run_options.register(NodeResource);
     run_options.register(QueryResource::new());
     run_options.register(FragmentResource);
@@ -82,8 +73,15 @@ fn vm() -> AskVm {
 }
 
 async fn ask(vm: web::Data<AskVm>, payload: web::Json<Payload>) -> Result<HttpResponse, Error> {
-    let Payload { code } = payload.into_inner();
-    let code = askql_parser::parse(code, false).unwrap();
+    let Payload { code: source } = payload.into_inner();
+    let code = match askql_parser::parse(source.clone(), false) {
+        Ok(code) => code,
+        Err(error) => {
+            return Ok(HttpResponse::BadRequest()
+                .content_type("text/plain")
+                .body(error.render(&source)))
+        }
+    };
     let result = vm.run(code, None, None).await;
     let (mut response, value) = match result {
         Ok(result) => (HttpResponse::Ok(), result),
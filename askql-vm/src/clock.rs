@@ -0,0 +1,25 @@
+use askql_parser::{DateTime, Utc};
+use std::time::SystemTime;
+
+/// Where the `now` resource reads the time, so tests can fix it.
+pub trait Clock: Sync + Send {
+    fn now(&self) -> DateTime<Utc>;
+}
+
+/// The system's clock, which `RunOptions` use by default.
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> DateTime<Utc> {
+        SystemTime::now().into()
+    }
+}
+
+/// A clock stopped at the given instant.
+pub struct FixedClock(pub DateTime<Utc>);
+
+impl Clock for FixedClock {
+    fn now(&self) -> DateTime<Utc> {
+        self.0
+    }
+}
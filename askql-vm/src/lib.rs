@@ -1,3 +1,4 @@
+pub mod clock;
 pub mod resource;
 pub mod resources;
 pub mod run;
@@ -22,7 +22,7 @@ pub trait Resource: Sync + Send {
             None => {
                 let mut args = Vec::new();
                 for param in code.params.unwrap_or(vec![]) {
-                    if let Ok(value) = vm.run(param, None, None).await {
+                    if let Ok(value) = vm.run(param, None, extended_options.clone()).await {
                         args.push(value)
                     }
                 }
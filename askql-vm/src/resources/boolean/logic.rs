@@ -0,0 +1,101 @@
+use crate::resource::Resource;
+use crate::run::AskVm;
+use askql_parser::{AskCode, AskCodeOrValue, Value};
+use async_trait::async_trait;
+use num_traits::Zero;
+use std::collections::HashMap;
+
+/// Whether `value` counts as true in a condition: everything but `null`,
+/// `false`, zero, NaN and the empty string, as in JavaScript.
+pub fn truthy(value: &Value) -> bool {
+    match value {
+        Value::Null => false,
+        Value::Boolean(boolean) => *boolean,
+        Value::Int(int) => *int != 0,
+        Value::BigInt(int) => !int.is_zero(),
+        Value::Decimal(decimal) => !decimal.is_zero(),
+        Value::Float(float) => *float != 0.0 && !float.is_nan(),
+        Value::String(string) => !string.is_empty(),
+        _ => true,
+    }
+}
+
+// Runs the params in order until one's truthiness is `stop`, returning that
+// one, or else the last; `empty` when there are none. Arguments already
+// evaluated, when called through `call`, are only combined.
+async fn short_circuit(
+    vm: &AskVm,
+    code: AskCode,
+    args: Option<Vec<Value>>,
+    extended_options: Option<HashMap<String, AskCodeOrValue>>,
+    stop: bool,
+) -> Value {
+    let params = match args {
+        Some(args) => args.into_iter().map(AskCodeOrValue::Value).collect(),
+        None => code.params.unwrap_or_default(),
+    };
+    let mut result = Value::Boolean(!stop);
+    for param in params {
+        result = vm
+            .run(param, None, extended_options.clone())
+            .await
+            .unwrap_or_default();
+        if truthy(&result) == stop {
+            break;
+        }
+    }
+    result
+}
+
+/// `&&`, which AskScript lowers to `and`: the first argument that is not
+/// truthy, or else the last one. The arguments after it are not evaluated.
+pub struct AndResource;
+
+#[async_trait]
+impl Resource for AndResource {
+    fn name(&self) -> String {
+        "and".to_string()
+    }
+    async fn compute(
+        &self,
+        vm: &AskVm,
+        code: AskCode,
+        args: Option<Vec<Value>>,
+        extended_options: Option<HashMap<String, AskCodeOrValue>>,
+    ) -> Value {
+        short_circuit(vm, code, args, extended_options, false).await
+    }
+}
+
+/// `||`, which AskScript lowers to `or`: the first truthy argument, or else
+/// the last one. The arguments after it are not evaluated.
+pub struct OrResource;
+
+#[async_trait]
+impl Resource for OrResource {
+    fn name(&self) -> String {
+        "or".to_string()
+    }
+    async fn compute(
+        &self,
+        vm: &AskVm,
+        code: AskCode,
+        args: Option<Vec<Value>>,
+        extended_options: Option<HashMap<String, AskCodeOrValue>>,
+    ) -> Value {
+        short_circuit(vm, code, args, extended_options, true).await
+    }
+}
+
+/// `!`, which AskScript lowers to `not`.
+pub struct NotResource;
+
+#[async_trait]
+impl Resource for NotResource {
+    fn name(&self) -> String {
+        "not".to_string()
+    }
+    async fn resolver(&self, args: Vec<Value>) -> Value {
+        Value::Boolean(!args.first().is_some_and(truthy))
+    }
+}
@@ -0,0 +1,27 @@
+use crate::resource::Resource;
+use askql_parser::Value;
+use async_trait::async_trait;
+
+/// The bytes of a value: `Bytes` as they are and strings as UTF-8.
+pub fn bytes_of(value: &Value) -> Option<&[u8]> {
+    match value {
+        Value::Bytes(bytes) => Some(bytes),
+        Value::String(string) => Some(string.as_bytes()),
+        _ => None,
+    }
+}
+
+pub struct ByteLengthResource;
+
+#[async_trait]
+impl Resource for ByteLengthResource {
+    fn name(&self) -> String {
+        "byteLength".to_string()
+    }
+    async fn resolver(&self, args: Vec<Value>) -> Value {
+        match args.first().and_then(bytes_of) {
+            Some(bytes) => Value::Int(bytes.len() as i64),
+            None => Value::Null,
+        }
+    }
+}
@@ -0,0 +1,22 @@
+use crate::resource::Resource;
+use askql_parser::Value;
+use async_trait::async_trait;
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+
+/// Decodes padded standard base64, as `Bytes` serialize to in JSON. Invalid
+/// input gives null.
+pub struct FromBase64Resource;
+
+#[async_trait]
+impl Resource for FromBase64Resource {
+    fn name(&self) -> String {
+        "fromBase64".to_string()
+    }
+    async fn resolver(&self, args: Vec<Value>) -> Value {
+        match args.first() {
+            Some(Value::String(text)) => STANDARD.decode(text).map_or(Value::Null, Value::Bytes),
+            _ => Value::Null,
+        }
+    }
+}
@@ -0,0 +1,19 @@
+use crate::resource::Resource;
+use askql_parser::Value;
+use async_trait::async_trait;
+
+/// Decodes hex in either case. Invalid input gives null.
+pub struct FromHexResource;
+
+#[async_trait]
+impl Resource for FromHexResource {
+    fn name(&self) -> String {
+        "fromHex".to_string()
+    }
+    async fn resolver(&self, args: Vec<Value>) -> Value {
+        match args.first() {
+            Some(Value::String(text)) => hex::decode(text).map_or(Value::Null, Value::Bytes),
+            _ => Value::Null,
+        }
+    }
+}
@@ -0,0 +1,12 @@
+pub mod byte_length;
+pub use byte_length::*;
+pub mod slice_bytes;
+pub use slice_bytes::*;
+pub mod to_base64;
+pub use to_base64::*;
+pub mod from_base64;
+pub use from_base64::*;
+pub mod to_hex;
+pub use to_hex::*;
+pub mod from_hex;
+pub use from_hex::*;
@@ -0,0 +1,38 @@
+use crate::resource::Resource;
+use askql_parser::Value;
+use async_trait::async_trait;
+
+/// `sliceBytes(bytes, start, end)` takes the bytes from `start` up to `end`,
+/// or to the last when `end` is left out. As in JavaScript's `slice`, negative
+/// indices count from the end and indices out of range are clamped.
+pub struct SliceBytesResource;
+
+// Where `index` falls in `len` bytes.
+fn position(index: i64, len: usize) -> usize {
+    if index < 0 {
+        len.saturating_sub(index.unsigned_abs() as usize)
+    } else {
+        (index as usize).min(len)
+    }
+}
+
+#[async_trait]
+impl Resource for SliceBytesResource {
+    fn name(&self) -> String {
+        "sliceBytes".to_string()
+    }
+    async fn resolver(&self, args: Vec<Value>) -> Value {
+        match (args.first(), args.get(1), args.get(2)) {
+            (Some(Value::Bytes(bytes)), Some(Value::Int(start)), end) => {
+                let end = match end {
+                    Some(Value::Int(end)) => position(*end, bytes.len()),
+                    None => bytes.len(),
+                    Some(_) => return Value::Null,
+                };
+                let start = position(*start, bytes.len());
+                Value::Bytes(bytes[start..end.max(start)].to_vec())
+            }
+            _ => Value::Null,
+        }
+    }
+}
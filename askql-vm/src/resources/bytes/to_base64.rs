@@ -0,0 +1,22 @@
+use super::byte_length::bytes_of;
+use crate::resource::Resource;
+use askql_parser::Value;
+use async_trait::async_trait;
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+
+/// Encodes bytes, or a string's UTF-8, as padded standard base64.
+pub struct ToBase64Resource;
+
+#[async_trait]
+impl Resource for ToBase64Resource {
+    fn name(&self) -> String {
+        "toBase64".to_string()
+    }
+    async fn resolver(&self, args: Vec<Value>) -> Value {
+        match args.first().and_then(bytes_of) {
+            Some(bytes) => Value::String(STANDARD.encode(bytes)),
+            None => Value::Null,
+        }
+    }
+}
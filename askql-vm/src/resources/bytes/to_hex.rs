@@ -0,0 +1,20 @@
+use super::byte_length::bytes_of;
+use crate::resource::Resource;
+use askql_parser::Value;
+use async_trait::async_trait;
+
+/// Encodes bytes, or a string's UTF-8, as lowercase hex.
+pub struct ToHexResource;
+
+#[async_trait]
+impl Resource for ToHexResource {
+    fn name(&self) -> String {
+        "toHex".to_string()
+    }
+    async fn resolver(&self, args: Vec<Value>) -> Value {
+        match args.first().and_then(bytes_of) {
+            Some(bytes) => Value::String(hex::encode(bytes)),
+            None => Value::Null,
+        }
+    }
+}
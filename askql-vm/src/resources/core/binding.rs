@@ -0,0 +1,45 @@
+use crate::resource::Resource;
+use crate::run::AskVm;
+use askql_parser::{AskCode, AskCodeOrValue, Value};
+use async_trait::async_trait;
+use std::collections::HashMap;
+
+/// `let(name, value)` evaluates `value` once and returns it. Run as a block
+/// statement, it also binds `name` to the value for the statements after it.
+pub struct LetResource;
+
+/// The name a block statement binds, when it is a `let`.
+pub fn binding(statement: &AskCodeOrValue) -> Option<&str> {
+    match statement {
+        AskCodeOrValue::AskCode(AskCode {
+            name,
+            params: Some(params),
+        }) if name == "let" => match params.first() {
+            Some(AskCodeOrValue::Value(Value::String(name))) => Some(name),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+#[async_trait]
+impl Resource for LetResource {
+    fn name(&self) -> String {
+        "let".to_string()
+    }
+    async fn compute(
+        &self,
+        vm: &AskVm,
+        code: AskCode,
+        _args: Option<Vec<Value>>,
+        extended_options: Option<HashMap<String, AskCodeOrValue>>,
+    ) -> Value {
+        match code.params.and_then(|params| params.into_iter().nth(1)) {
+            Some(value) => vm
+                .run(value, None, extended_options)
+                .await
+                .unwrap_or_default(),
+            None => Value::Null,
+        }
+    }
+}
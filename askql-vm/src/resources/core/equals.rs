@@ -12,19 +12,20 @@ impl Resource for EqualsResource {
     fn name(&self) -> String {
         "equals".to_string()
     }
+    // True when all the arguments are equal, numbers compared by value.
     async fn resolver(&self, args: Vec<Value>) -> Value {
-        let (is_equal, _) =
-            args.into_iter()
-                .enumerate()
-                .fold((true, Value::Null), |mut acc, (idx, val)| {
-                    if idx == 0 {
-                        acc.1 = val;
-                    } else {
-                        acc.0 = acc.1 == val;
-                        acc.1 = val;
-                    }
-                    acc
-                });
-        Value::Boolean(is_equal)
+        Value::Boolean(args.windows(2).all(|pair| pair[0] == pair[1]))
+    }
+}
+
+pub struct NotEqualsResource;
+
+#[async_trait]
+impl Resource for NotEqualsResource {
+    fn name(&self) -> String {
+        "!=".to_string()
+    }
+    async fn resolver(&self, args: Vec<Value>) -> Value {
+        Value::Boolean(args.windows(2).any(|pair| pair[0] != pair[1]))
     }
 }
@@ -1,3 +1,4 @@
+use super::binding::binding;
 use crate::resource::Resource;
 use crate::run::AskVm;
 use askql_parser::*;
@@ -14,39 +15,42 @@ pub trait FunResource: Resource {
         args: Option<Vec<Value>>,
         extended_options: Option<HashMap<String, AskCodeOrValue>>,
     ) -> Value {
-        match args {
-            Some(args) => {
-                let AskCode { params, .. } = code;
-                let mut last_result = Value::Null;
-                let cloned_opts = extended_options.clone();
-                if let Some(statements) = params {
-                    for statement in statements {
-                        if let Ok(value) = vm.run(statement, None, cloned_opts.clone()).await {
-                            last_result = value;
-                        }
-                    }
+        let AskCode { params, .. } = code;
+        let statements = params.unwrap_or_default();
+        let mut last_result = Value::Null;
+        if args.is_none()
+            && !statements
+                .iter()
+                .any(|statement| binding(statement).is_some())
+        {
+            // Nothing is bound, so the statements cannot see each other and
+            // may run concurrently.
+            let cloned_opts = extended_options.clone();
+            let statements = join_all(
+                statements
+                    .into_iter()
+                    .map(move |statement| vm.run(statement, None, cloned_opts.clone())),
+            )
+            .await;
+            for statement in statements {
+                if let Ok(value) = statement {
+                    last_result = value;
                 }
-                last_result
             }
-            None => {
-                let AskCode { params, .. } = code;
-                let mut last_result = Value::Null;
-                if let Some(statements) = params {
-                    let cloned_opts = extended_options.clone();
-                    let statements = join_all(
-                        statements
-                            .into_iter()
-                            .map(move |statement| vm.run(statement, None, cloned_opts.clone())),
-                    )
-                    .await;
-                    for statement in statements {
-                        if let Ok(value) = statement {
-                            last_result = value;
-                        }
-                    }
+            return last_result;
+        }
+        let mut scope = extended_options;
+        for statement in statements {
+            let name = binding(&statement).map(str::to_string);
+            if let Ok(value) = vm.run(statement, None, scope.clone()).await {
+                if let Some(name) = name {
+                    scope
+                        .get_or_insert_with(HashMap::new)
+                        .insert(name, AskCodeOrValue::Value(value.clone()));
                 }
-                last_result
+                last_result = value;
             }
         }
+        last_result
     }
 }
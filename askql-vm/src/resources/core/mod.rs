@@ -12,3 +12,5 @@ pub mod fragment;
 pub use fragment::*;
 pub mod equals;
 pub use equals::*;
+pub mod binding;
+pub use binding::*;
@@ -1,6 +1,6 @@
 use crate::resource::Resource;
 use crate::run::AskVm;
-use askql_parser::{AskCode, AskCodeOrValue, Value};
+use askql_parser::{AskCode, AskCodeOrValue, Object, Value};
 use async_trait::async_trait;
 use futures::future::join_all;
 use std::collections::HashMap;
@@ -13,12 +13,14 @@ impl NodeResource {
         vm: &AskVm,
         value: AskCodeOrValue,
         children: Vec<AskCodeOrValue>,
+        scope: Option<HashMap<String, AskCodeOrValue>>,
     ) -> Value {
         match value {
             AskCodeOrValue::Value(value) => {
                 let cloned_val = value.clone();
                 match value {
                     Value::Object(obj) => {
+                        let scope = &scope;
                         let futures = children.into_iter().map(|child| match child {
                             AskCodeOrValue::AskCode(code) => {
                                 async {
@@ -33,7 +35,7 @@ impl NodeResource {
                                                 .run(
                                                     name_getter,
                                                     Some(vec![cloned_val.clone()]),
-                                                    None,
+                                                    scope.clone(),
                                                 )
                                                 .await
                                                 .unwrap_or_default();
@@ -43,7 +45,11 @@ impl NodeResource {
                                             ));
                                             // dbg!(&cloned_val);
                                             let value = vm
-                                                .run(code, Some(vec![cloned_val.clone()]), None)
+                                                .run(
+                                                    code,
+                                                    Some(vec![cloned_val.clone()]),
+                                                    scope.clone(),
+                                                )
                                                 .await
                                                 .unwrap_or_default();
                                             return Value::List(vec![name, value]);
@@ -76,31 +82,35 @@ impl NodeResource {
             }
             code => {
                 dbg!("Holla holla!");
-                vm.run(code, None, None).await.unwrap_or_default()
+                vm.run(code, None, scope).await.unwrap_or_default()
             }
         }
     }
 
+    // The fields of the object in `args`, in scope over the names bound outside.
     fn args_to_extended_options(
         &self,
         args: Option<Vec<Value>>,
+        scope: Option<HashMap<String, AskCodeOrValue>>,
     ) -> Option<std::collections::HashMap<String, AskCodeOrValue>> {
         match args {
             Some(mut args) => {
                 if args.len() > 0 {
                     match args.remove(0) {
-                        Value::Object(obj) => Some(
-                            obj.into_iter()
-                                .map(|(k, v)| (k, AskCodeOrValue::Value(v)))
-                                .collect(),
-                        ),
-                        _ => None,
+                        Value::Object(obj) => {
+                            let mut options = scope.unwrap_or_default();
+                            options.extend(
+                                obj.into_iter().map(|(k, v)| (k, AskCodeOrValue::Value(v))),
+                            );
+                            Some(options)
+                        }
+                        _ => scope,
                     }
                 } else {
-                    None
+                    scope
                 }
             }
-            None => None,
+            None => scope,
         }
     }
 }
@@ -121,7 +131,8 @@ impl Resource for NodeResource {
         match params {
             Some(mut params) if params.len() >= 1 => {
                 let children: Vec<AskCodeOrValue> = params.drain(2..).collect();
-                let extended_options = self.args_to_extended_options(args);
+                let scope = extended_options;
+                let extended_options = self.args_to_extended_options(args, scope.clone());
                 // dbg!(&extended_options);
                 let value_getter: AskCodeOrValue = params.remove(1);
                 // dbg!(&value_getter);
@@ -133,12 +144,17 @@ impl Resource for NodeResource {
                 if let Value::List(list) = value {
                     Value::List(
                         join_all(list.into_iter().map(|v| {
-                            self.process(vm, AskCodeOrValue::new_value(v), children.clone())
+                            self.process(
+                                vm,
+                                AskCodeOrValue::new_value(v),
+                                children.clone(),
+                                scope.clone(),
+                            )
                         }))
                         .await,
                     )
                 } else {
-                    self.process(vm, AskCodeOrValue::new_value(value), children)
+                    self.process(vm, AskCodeOrValue::new_value(value), children, scope)
                         .await
                 }
             }
@@ -177,7 +193,7 @@ impl Resource for QueryResource {
                     params.map(|children| {
                         let mut params = vec![
                             AskCodeOrValue::Value(Value::String("value".to_string())),
-                            AskCodeOrValue::Value(Value::Object(std::collections::BTreeMap::new())),
+                            AskCodeOrValue::Value(Value::Object(Object::new())),
                         ];
                         params.extend(children);
                         params
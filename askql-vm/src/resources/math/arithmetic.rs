@@ -0,0 +1,175 @@
+use askql_parser::{BigDecimal, BigInt, Value};
+use num_traits::{ToPrimitive, Zero};
+use std::cmp::Ordering;
+use std::convert::TryFrom;
+
+// Two numbers widened to a common kind. `Int`s overflow into `BigInt`s and
+// anything combined with a `Decimal` becomes one, so exact values stay exact;
+// otherwise a `Float` makes the result a `Float`, as it always has.
+enum Operands {
+    Int(i64, i64),
+    BigInt(BigInt, BigInt),
+    Decimal(BigDecimal, BigDecimal),
+    Float(f64, f64),
+}
+
+fn operands(a: &Value, b: &Value) -> Option<Operands> {
+    let decimal = |value: &Value| match value {
+        Value::Int(int) => Some(BigDecimal::from(*int)),
+        Value::BigInt(int) => Some(BigDecimal::from(int.clone())),
+        Value::Decimal(decimal) => Some(decimal.clone()),
+        // The shortest decimal that reads back as the float, so `0.1` stays
+        // `0.1` rather than its binary expansion.
+        Value::Float(float) if float.is_finite() => float.to_string().parse().ok(),
+        _ => None,
+    };
+    let float = |value: &Value| match value {
+        Value::Int(int) => Some(*int as f64),
+        Value::BigInt(int) => int.to_f64(),
+        Value::Decimal(decimal) => decimal.to_f64(),
+        Value::Float(float) => Some(*float),
+        _ => None,
+    };
+    let big_int = |value: &Value| match value {
+        Value::Int(int) => Some(BigInt::from(*int)),
+        Value::BigInt(int) => Some(int.clone()),
+        _ => None,
+    };
+    Some(match (a, b) {
+        (Value::Int(a), Value::Int(b)) => Operands::Int(*a, *b),
+        (Value::Decimal(_), _) | (_, Value::Decimal(_)) => match (decimal(a), decimal(b)) {
+            (Some(a), Some(b)) => Operands::Decimal(a, b),
+            // Only NaN and the infinities have no decimal form.
+            _ => Operands::Float(float(a)?, float(b)?),
+        },
+        (Value::Float(_), _) | (_, Value::Float(_)) => Operands::Float(float(a)?, float(b)?),
+        _ => Operands::BigInt(big_int(a)?, big_int(b)?),
+    })
+}
+
+// Big integers that fit are turned back into `Int`s.
+fn big_int(int: BigInt) -> Value {
+    match i64::try_from(&int) {
+        Ok(int) => Value::Int(int),
+        Err(_) => Value::BigInt(int),
+    }
+}
+
+pub fn is_number(value: &Value) -> bool {
+    matches!(
+        value,
+        Value::Int(_) | Value::BigInt(_) | Value::Decimal(_) | Value::Float(_)
+    )
+}
+
+pub fn is_temporal(value: &Value) -> bool {
+    matches!(value, Value::DateTime(_) | Value::Duration(_))
+}
+
+/// `a + b` for two numbers, two durations or a date-time and a duration, or
+/// `None` for other operands and date-times out of range.
+pub fn add(a: &Value, b: &Value) -> Option<Value> {
+    match (a, b) {
+        (Value::DateTime(date_time), Value::Duration(duration))
+        | (Value::Duration(duration), Value::DateTime(date_time)) => {
+            return date_time.checked_add_signed(*duration).map(Value::DateTime)
+        }
+        (Value::Duration(a), Value::Duration(b)) => return a.checked_add(b).map(Value::Duration),
+        _ => {}
+    }
+    Some(match operands(a, b)? {
+        Operands::Int(a, b) => match a.checked_add(b) {
+            Some(sum) => Value::Int(sum),
+            None => big_int(BigInt::from(a) + b),
+        },
+        Operands::BigInt(a, b) => big_int(a + b),
+        Operands::Decimal(a, b) => Value::Decimal(a + b),
+        Operands::Float(a, b) => Value::Float(a + b),
+    })
+}
+
+/// `a - b` for two numbers, two durations, a date-time and a duration, or
+/// two date-times, which gives the duration between them; `None` for other
+/// operands and date-times out of range.
+pub fn subtract(a: &Value, b: &Value) -> Option<Value> {
+    match (a, b) {
+        (Value::DateTime(date_time), Value::Duration(duration)) => {
+            return date_time.checked_sub_signed(*duration).map(Value::DateTime)
+        }
+        (Value::DateTime(a), Value::DateTime(b)) => {
+            return Some(Value::Duration(a.signed_duration_since(*b)))
+        }
+        (Value::Duration(a), Value::Duration(b)) => return a.checked_sub(b).map(Value::Duration),
+        _ => {}
+    }
+    Some(match operands(a, b)? {
+        Operands::Int(a, b) => match a.checked_sub(b) {
+            Some(difference) => Value::Int(difference),
+            None => big_int(BigInt::from(a) - b),
+        },
+        Operands::BigInt(a, b) => big_int(a - b),
+        Operands::Decimal(a, b) => Value::Decimal(a - b),
+        Operands::Float(a, b) => Value::Float(a - b),
+    })
+}
+
+/// `a * b`, or `None` when either is not a number.
+pub fn multiply(a: &Value, b: &Value) -> Option<Value> {
+    Some(match operands(a, b)? {
+        Operands::Int(a, b) => match a.checked_mul(b) {
+            Some(product) => Value::Int(product),
+            None => big_int(BigInt::from(a) * b),
+        },
+        Operands::BigInt(a, b) => big_int(a * b),
+        Operands::Decimal(a, b) => Value::Decimal(a * b),
+        Operands::Float(a, b) => Value::Float(a * b),
+    })
+}
+
+/// Compares two numbers by value, or `None` when either is not a number or
+/// one is NaN.
+pub fn compare(a: &Value, b: &Value) -> Option<Ordering> {
+    match operands(a, b)? {
+        Operands::Int(a, b) => Some(a.cmp(&b)),
+        Operands::BigInt(a, b) => Some(a.cmp(&b)),
+        Operands::Decimal(a, b) => Some(a.cmp(&b)),
+        Operands::Float(a, b) => a.partial_cmp(&b),
+    }
+}
+
+/// `a / b`, or `None` when either is not a number or `b` is zero. Integers
+/// that do not divide exactly give a `Float`, as in AskScript's JavaScript.
+pub fn divide(a: &Value, b: &Value) -> Option<Value> {
+    Some(match operands(a, b)? {
+        Operands::Int(_, 0) => return None,
+        Operands::Int(a, b) if a.checked_rem(b).unwrap_or(0) == 0 => match a.checked_div(b) {
+            Some(quotient) => Value::Int(quotient),
+            // Only `i64::MIN / -1` overflows.
+            None => big_int(-BigInt::from(a)),
+        },
+        Operands::Int(a, b) => Value::Float(a as f64 / b as f64),
+        Operands::BigInt(_, b) if b.is_zero() => return None,
+        Operands::BigInt(a, b) if (&a % &b).is_zero() => big_int(a / b),
+        Operands::BigInt(a, b) => Value::Float(a.to_f64()? / b.to_f64()?),
+        Operands::Decimal(_, b) if b.is_zero() => return None,
+        Operands::Decimal(a, b) => Value::Decimal(a / b),
+        Operands::Float(_, 0.0) => return None,
+        Operands::Float(a, b) => Value::Float(a / b),
+    })
+}
+
+/// The remainder of `a / b`, with the sign of `a`, or `None` when either is
+/// not a number or `b` is zero.
+pub fn remainder(a: &Value, b: &Value) -> Option<Value> {
+    Some(match operands(a, b)? {
+        Operands::Int(_, 0) => return None,
+        // Only `i64::MIN % -1` overflows, and its remainder is zero.
+        Operands::Int(a, b) => Value::Int(a.checked_rem(b).unwrap_or(0)),
+        Operands::BigInt(_, b) if b.is_zero() => return None,
+        Operands::BigInt(a, b) => big_int(a % b),
+        Operands::Decimal(_, b) if b.is_zero() => return None,
+        Operands::Decimal(a, b) => Value::Decimal(a % b),
+        Operands::Float(_, 0.0) => return None,
+        Operands::Float(a, b) => Value::Float(a % b),
+    })
+}
@@ -0,0 +1,73 @@
+use super::arithmetic::*;
+use crate::resource::Resource;
+use askql_parser::Value;
+use async_trait::async_trait;
+use std::cmp::Ordering;
+
+// Numbers compare by value; strings, date-times and durations only with their
+// own kind. Anything else does not compare.
+fn order(a: &Value, b: &Value) -> Option<Ordering> {
+    match (a, b) {
+        (Value::String(a), Value::String(b)) => Some(a.cmp(b)),
+        (Value::DateTime(a), Value::DateTime(b)) => Some(a.cmp(b)),
+        (Value::Duration(a), Value::Duration(b)) => Some(a.cmp(b)),
+        _ => compare(a, b),
+    }
+}
+
+// True when each argument orders against the next as `accept` wants, so
+// `<(1, 2, 3)` holds. Values that do not compare make it false.
+fn chain(args: &[Value], accept: fn(Ordering) -> bool) -> Value {
+    Value::Boolean(
+        args.windows(2)
+            .all(|pair| order(&pair[0], &pair[1]).is_some_and(accept)),
+    )
+}
+
+pub struct LessThanResource;
+
+#[async_trait]
+impl Resource for LessThanResource {
+    fn name(&self) -> String {
+        "<".to_string()
+    }
+    async fn resolver(&self, args: Vec<Value>) -> Value {
+        chain(&args, Ordering::is_lt)
+    }
+}
+
+pub struct LessOrEqualResource;
+
+#[async_trait]
+impl Resource for LessOrEqualResource {
+    fn name(&self) -> String {
+        "<=".to_string()
+    }
+    async fn resolver(&self, args: Vec<Value>) -> Value {
+        chain(&args, Ordering::is_le)
+    }
+}
+
+pub struct GreaterThanResource;
+
+#[async_trait]
+impl Resource for GreaterThanResource {
+    fn name(&self) -> String {
+        ">".to_string()
+    }
+    async fn resolver(&self, args: Vec<Value>) -> Value {
+        chain(&args, Ordering::is_gt)
+    }
+}
+
+pub struct GreaterOrEqualResource;
+
+#[async_trait]
+impl Resource for GreaterOrEqualResource {
+    fn name(&self) -> String {
+        ">=".to_string()
+    }
+    async fn resolver(&self, args: Vec<Value>) -> Value {
+        chain(&args, Ordering::is_ge)
+    }
+}
@@ -0,0 +1,38 @@
+use super::arithmetic::*;
+use crate::resource::Resource;
+use askql_parser::Value;
+use async_trait::async_trait;
+
+// Folds the numbers among `args` from the first; a division by zero makes
+// the whole result `null`.
+fn fold(args: Vec<Value>, step: fn(&Value, &Value) -> Option<Value>) -> Value {
+    let mut operands = args.into_iter().filter(is_number);
+    let first = operands.next().unwrap_or(Value::Null);
+    operands
+        .try_fold(first, |result, value| step(&result, &value))
+        .unwrap_or(Value::Null)
+}
+
+pub struct DivideResource;
+
+#[async_trait]
+impl Resource for DivideResource {
+    fn name(&self) -> String {
+        "/".to_string()
+    }
+    async fn resolver(&self, args: Vec<Value>) -> Value {
+        fold(args, divide)
+    }
+}
+
+pub struct RemainderResource;
+
+#[async_trait]
+impl Resource for RemainderResource {
+    fn name(&self) -> String {
+        "%".to_string()
+    }
+    async fn resolver(&self, args: Vec<Value>) -> Value {
+        fold(args, remainder)
+    }
+}
@@ -1,7 +1,9 @@
+use super::arithmetic::*;
 use crate::resource::Resource;
 use crate::run::AskVm;
 use askql_parser::{AskCode, AskCodeOrValue, Value};
 use async_trait::async_trait;
+use std::cmp::Ordering;
 
 pub struct MaxResource;
 
@@ -28,41 +30,15 @@ impl Resource for MaxResource {
         "max".to_string()
     }
     async fn resolver(&self, args: Vec<Value>) -> Value {
-        if args.len() > 0 {
-            let flattened = flatten(args);
-            flattened.into_iter().fold(Value::Null, |acc, val| {
-                if acc.is_null() {
-                    match val {
-                        Value::Int(int) => Value::Int(int),
-                        Value::Float(float) => Value::Float(float),
-                        _ => acc,
-                    }
+        flatten(args)
+            .into_iter()
+            .filter(is_number)
+            .fold(Value::Null, |max, value| {
+                if max.is_null() || compare(&value, &max) == Some(Ordering::Greater) {
+                    value
                 } else {
-                    match (acc, val) {
-                        (Value::Int(a), Value::Int(b)) => Value::Int(if b > a { b } else { a }),
-                        (Value::Int(a), Value::Float(b)) => {
-                            if b > a as f32 {
-                                Value::Float(b)
-                            } else {
-                                Value::Int(a)
-                            }
-                        }
-                        (Value::Float(a), Value::Int(b)) => {
-                            if b as f32 > a {
-                                Value::Int(b)
-                            } else {
-                                Value::Float(a)
-                            }
-                        }
-                        (Value::Float(a), Value::Float(b)) => {
-                            Value::Float(if b > a { b } else { a })
-                        }
-                        (acc, _) => acc,
-                    }
+                    max
                 }
             })
-        } else {
-            Value::Null
-        }
     }
 }
@@ -1,3 +1,5 @@
+pub mod arithmetic;
+pub use arithmetic::*;
 pub mod sum;
 pub use sum::*;
 pub mod minus;
@@ -6,3 +8,7 @@ pub mod times;
 pub use times::*;
 pub mod max;
 pub use max::*;
+pub mod compare;
+pub use compare::*;
+pub mod divide;
+pub use divide::*;
@@ -1,3 +1,4 @@
+use super::arithmetic::*;
 use crate::resource::Resource;
 use crate::run::AskVm;
 use askql_parser::{AskCode, AskCodeOrValue, Value};
@@ -11,27 +12,12 @@ impl Resource for SumResource {
         "+".to_string()
     }
     async fn resolver(&self, args: Vec<Value>) -> Value {
-        let (float_sum, int_sum, has_float, has_int) =
-            args.into_iter()
-                .fold((0.0, 0, false, false), |mut acc, val| match val {
-                    Value::Int(integer) => {
-                        acc.3 = true;
-                        acc.1 += integer;
-                        acc
-                    }
-                    Value::Float(float) => {
-                        acc.2 = true;
-                        acc.0 += float;
-                        acc
-                    }
-                    _ => acc,
-                });
-        if has_float && has_int {
-            Value::Float(float_sum + (int_sum as f32))
-        } else if has_float {
-            Value::Float(float_sum)
-        } else {
-            Value::Int(int_sum)
-        }
+        // Starting from the first operand rather than zero lets date-times
+        // be added to.
+        let mut operands = args
+            .into_iter()
+            .filter(|value| is_number(value) || is_temporal(value));
+        let first = operands.next().unwrap_or(Value::Int(0));
+        operands.fold(first, |sum, value| add(&sum, &value).unwrap_or(sum))
     }
 }
@@ -6,3 +6,11 @@ pub mod string;
 pub use string::*;
 pub mod list;
 pub use list::*;
+pub mod object;
+pub use object::*;
+pub mod time;
+pub use time::*;
+pub mod bytes;
+pub use bytes::*;
+pub mod boolean;
+pub use boolean::*;
@@ -0,0 +1,30 @@
+use crate::resource::Resource;
+use askql_parser::Value;
+use async_trait::async_trait;
+
+/// Builds an object from `list(key, value)` entries, as the parsers emit for
+/// object literals. Entries whose key is not a string are skipped.
+pub struct ObjectResource;
+
+#[async_trait]
+impl Resource for ObjectResource {
+    fn name(&self) -> String {
+        "object".to_string()
+    }
+    async fn resolver(&self, args: Vec<Value>) -> Value {
+        Value::Object(
+            args.into_iter()
+                .filter_map(|entry| match entry {
+                    Value::List(mut entry) if entry.len() == 2 => {
+                        let value = entry.remove(1);
+                        match entry.remove(0) {
+                            Value::String(key) => Some((key, value)),
+                            _ => None,
+                        }
+                    }
+                    _ => None,
+                })
+                .collect(),
+        )
+    }
+}
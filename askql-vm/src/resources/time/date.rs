@@ -0,0 +1,26 @@
+use crate::resource::Resource;
+use askql_parser::{parse_date_time, DateTime, Value};
+use async_trait::async_trait;
+
+/// Makes a date-time from ISO-8601 text or from milliseconds since the Unix
+/// epoch, as JavaScript's `Date` does. Anything else gives null.
+pub struct DateResource;
+
+#[async_trait]
+impl Resource for DateResource {
+    fn name(&self) -> String {
+        "date".to_string()
+    }
+    async fn resolver(&self, mut args: Vec<Value>) -> Value {
+        if args.is_empty() {
+            return Value::Null;
+        }
+        let date_time = match args.remove(0) {
+            Value::String(text) => parse_date_time(&text),
+            Value::Int(millis) => DateTime::from_timestamp_millis(millis),
+            Value::DateTime(date_time) => Some(date_time),
+            _ => None,
+        };
+        date_time.map_or(Value::Null, Value::DateTime)
+    }
+}
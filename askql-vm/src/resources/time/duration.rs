@@ -0,0 +1,26 @@
+use crate::resource::Resource;
+use askql_parser::{parse_duration, TimeDelta, Value};
+use async_trait::async_trait;
+
+/// Makes a duration from ISO-8601 text such as `PT1H30M` or from a number of
+/// milliseconds. Anything else gives null.
+pub struct DurationResource;
+
+#[async_trait]
+impl Resource for DurationResource {
+    fn name(&self) -> String {
+        "duration".to_string()
+    }
+    async fn resolver(&self, mut args: Vec<Value>) -> Value {
+        if args.is_empty() {
+            return Value::Null;
+        }
+        let duration = match args.remove(0) {
+            Value::String(text) => parse_duration(&text),
+            Value::Int(millis) => TimeDelta::try_milliseconds(millis),
+            Value::Duration(duration) => Some(duration),
+            _ => None,
+        };
+        duration.map_or(Value::Null, Value::Duration)
+    }
+}
@@ -0,0 +1,8 @@
+pub mod date;
+pub use date::*;
+pub mod duration;
+pub use duration::*;
+pub mod now;
+pub use now::*;
+pub mod to_iso_string;
+pub use to_iso_string::*;
@@ -0,0 +1,24 @@
+use crate::resource::Resource;
+use crate::run::AskVm;
+use askql_parser::{AskCode, AskCodeOrValue, Value};
+use async_trait::async_trait;
+use std::collections::HashMap;
+
+/// The current time, read from the clock in the VM's `RunOptions`.
+pub struct NowResource;
+
+#[async_trait]
+impl Resource for NowResource {
+    fn name(&self) -> String {
+        "now".to_string()
+    }
+    async fn compute(
+        &self,
+        vm: &AskVm,
+        _code: AskCode,
+        _args: Option<Vec<Value>>,
+        _extended_options: Option<HashMap<String, AskCodeOrValue>>,
+    ) -> Value {
+        Value::DateTime(vm.now())
+    }
+}
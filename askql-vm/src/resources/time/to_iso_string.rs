@@ -0,0 +1,20 @@
+use crate::resource::Resource;
+use askql_parser::{format_date_time, format_duration, Value};
+use async_trait::async_trait;
+
+/// Writes a date-time or a duration as ISO-8601 text; other values give null.
+pub struct ToIsoStringResource;
+
+#[async_trait]
+impl Resource for ToIsoStringResource {
+    fn name(&self) -> String {
+        "toISOString".to_string()
+    }
+    async fn resolver(&self, args: Vec<Value>) -> Value {
+        match args.first() {
+            Some(Value::DateTime(date_time)) => Value::String(format_date_time(date_time)),
+            Some(Value::Duration(duration)) => Value::String(format_duration(duration)),
+            _ => Value::Null,
+        }
+    }
+}
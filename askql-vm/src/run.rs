@@ -1,7 +1,8 @@
+use crate::clock::{Clock, SystemClock};
 use crate::r#type::ScalarType;
 use crate::resource::Resource;
 use crate::typed::{typed, untyped};
-use askql_parser::{AskCode, AskCodeOrValue, Value};
+use askql_parser::{AskCode, AskCodeOrValue, DateTime, Utc, Value, RESERVED_WORDS};
 use futures::future::{BoxFuture, FutureExt};
 use std::boxed::Box;
 use std::collections::HashMap;
@@ -10,16 +11,27 @@ use std::sync::Arc;
 pub struct RunOptions {
     pub resources: HashMap<String, Box<dyn Resource>>,
     pub values: HashMap<String, AskCodeOrValue>,
+    /// Read by the `now` resource; the system clock unless replaced.
+    pub clock: Box<dyn Clock>,
 }
 
 impl RunOptions {
+    /// Resources and values named with one of the parser's `RESERVED_WORDS`
+    /// are left out, as `register_with_name` refuses them too.
     pub fn new(resources: Vec<Box<dyn Resource>>, values: HashMap<String, AskCodeOrValue>) -> Self {
+        let resources = resources
+            .into_iter()
+            .map(|resource| (resource.name(), resource))
+            .filter(|(name, _)| !is_reserved(name))
+            .collect();
+        let values = values
+            .into_iter()
+            .filter(|(name, _)| !is_reserved(name))
+            .collect();
         Self {
-            resources: resources
-                .into_iter()
-                .map(|resource| (resource.name(), resource))
-                .collect(),
+            resources,
             values,
+            clock: Box::new(SystemClock),
         }
     }
 
@@ -28,8 +40,14 @@ impl RunOptions {
         self.register_with_name(resource, name)
     }
 
-    pub fn register_with_name<T: Resource + 'static>(&mut self, resource: T, name: String) -> Option<T> {
-        if self.resources.contains_key(&name) {
+    /// Registers `resource` under `name`, handing it back when the name is
+    /// taken or is one of the parser's `RESERVED_WORDS`.
+    pub fn register_with_name<T: Resource + 'static>(
+        &mut self,
+        resource: T,
+        name: String,
+    ) -> Option<T> {
+        if self.resources.contains_key(&name) || is_reserved(&name) {
             Some(resource)
         } else {
             self.resources.insert(name, Box::new(resource));
@@ -38,6 +56,10 @@ impl RunOptions {
     }
 }
 
+fn is_reserved(name: &str) -> bool {
+    RESERVED_WORDS.contains(&name)
+}
+
 pub struct AskVm {
     options: Arc<RunOptions>,
 }
@@ -49,6 +71,11 @@ impl AskVm {
         }
     }
 
+    /// The current time on the options' clock.
+    pub fn now(&self) -> DateTime<Utc> {
+        self.options.clock.now()
+    }
+
     pub fn run(
         &self,
         code: AskCodeOrValue,
@@ -59,16 +86,14 @@ impl AskVm {
         async move {
             match code {
                 AskCodeOrValue::Value(Value::Number(number)) => {
-                    if number.is_float() {
-                        Ok(Value::Float(number.to_float().unwrap_or(0.0)))
-                    } else {
-                        Ok(Value::Int(number.to_int().unwrap_or(0)))
-                    }
+                    Ok(number.to_value().unwrap_or(Value::Null))
                 }
                 AskCodeOrValue::Value(value) => Ok(value),
                 AskCodeOrValue::AskCode(code) => {
                     // dbg!(&code.name);
-                    if let Some(ext_opt) = &extended_options {
+                    // Scoped names are looked up by identifiers only, so that a
+                    // binding cannot shadow the resource behind a node like `list(...)`.
+                    if let (Some(ext_opt), None) = (&extended_options, &code.params) {
                         match ext_opt.get(&code.name) {
                             Some(value) => return self.run(value.clone(), args, None).await,
                             None => {
@@ -99,25 +124,59 @@ impl AskVm {
 mod tests {
     use super::*;
     use crate::resources::*;
-    use askql_parser::{AskCodeOrValue, Value};
+    use askql_macros::IntoValue;
+    use askql_parser::{AskCodeOrValue, IntoValue, Value};
+
+    fn new_vm(
+        resources: Vec<Box<dyn crate::resource::Resource>>,
+        values: HashMap<String, AskCodeOrValue>,
+    ) -> AskVm {
+        AskVm::new(new_options(resources, values))
+    }
 
-    fn new_vm(resources: Vec<Box<dyn crate::resource::Resource>>, values: HashMap<String, AskCodeOrValue>) -> AskVm {
+    fn new_options(
+        resources: Vec<Box<dyn crate::resource::Resource>>,
+        values: HashMap<String, AskCodeOrValue>,
+    ) -> RunOptions {
         let mut run_options = RunOptions::new(resources, values);
         run_options.register(AskResource);
         run_options.register(CallResource);
         run_options.register(GetResource);
+        run_options.register(LetResource);
         run_options.register(SumResource);
         run_options.register(MinusResource);
         run_options.register(TimesResource);
         run_options.register(ConcatResource);
         run_options.register(MaxResource);
+        run_options.register(EqualsResource);
+        run_options.register(NotEqualsResource);
+        run_options.register(DivideResource);
+        run_options.register(RemainderResource);
+        run_options.register(AndResource);
+        run_options.register(OrResource);
+        run_options.register(NotResource);
         run_options.register(ListResource);
+        run_options.register(ObjectResource);
         run_options.register(NodeResource);
         run_options.register(QueryResource::new());
         run_options.register(FragmentResource);
         run_options.register(ToLowerCaseResource);
         run_options.register(ToUpperCaseResource);
-        AskVm::new(run_options)
+        run_options.register(LessThanResource);
+        run_options.register(LessOrEqualResource);
+        run_options.register(GreaterThanResource);
+        run_options.register(GreaterOrEqualResource);
+        run_options.register(DateResource);
+        run_options.register(DurationResource);
+        run_options.register(NowResource);
+        run_options.register(ToIsoStringResource);
+        run_options.register(ByteLengthResource);
+        run_options.register(SliceBytesResource);
+        run_options.register(ToBase64Resource);
+        run_options.register(FromBase64Resource);
+        run_options.register(ToHexResource);
+        run_options.register(FromHexResource);
+        run_options
     }
 
     #[tokio::test]
@@ -144,6 +203,80 @@ mod tests {
         assert_eq!(Ok(Value::Float(-14.2)), result);
     }
 
+    #[tokio::test]
+    async fn wide_numbers() {
+        let vm = new_vm(vec![], HashMap::new());
+        let run = |code: &str| {
+            let code = askql_parser::parse(code.to_string(), false).unwrap();
+            vm.run(code, None, None)
+        };
+        assert_eq!(
+            run("call(get('+'), 9007199254740992, 1)").await,
+            Ok(Value::Int(9007199254740993))
+        );
+        assert_eq!(
+            run("call(get('+'), 0.1, 0.2)").await,
+            Ok(Value::Float(0.30000000000000004))
+        );
+        assert_eq!(run("9223372036854775807").await, Ok(Value::Int(i64::MAX)));
+        assert_eq!(
+            run("call(get('-'), -9223372036854775807, 1)").await,
+            Ok(Value::Int(i64::MIN))
+        );
+    }
+
+    #[tokio::test]
+    async fn big_numbers() {
+        let vm = new_vm(vec![], HashMap::new());
+        let run = |code: &str| {
+            let code = askql_parser::parse(code.to_string(), false).unwrap();
+            vm.run(code, None, None)
+        };
+        let big = |text: &str| Ok(Value::BigInt(text.parse().unwrap()));
+        let decimal = |text: &str| Ok(Value::Decimal(text.parse().unwrap()));
+        assert_eq!(
+            run("call(get('+'), 9223372036854775807, 1)").await,
+            big("9223372036854775808")
+        );
+        assert_eq!(
+            run("call(get('*'), 4294967296, 4294967296, 2)").await,
+            big("36893488147419103232")
+        );
+        assert_eq!(
+            run("call(get('-'), 18446744073709551616, 18446744073709551615)").await,
+            Ok(Value::Int(1))
+        );
+        assert_eq!(
+            run("call(get('+'), 0.10000000000000000001, 0.2, 1)").await,
+            decimal("1.30000000000000000001")
+        );
+        assert_eq!(
+            run("call(get('max'), 1.5, 18446744073709551616, 0.10000000000000000001)").await,
+            big("18446744073709551616")
+        );
+        assert_eq!(
+            run("call(get('+'), 18446744073709551616, 0.5)").await,
+            Ok(Value::Float(18446744073709551616.5))
+        );
+    }
+
+    #[tokio::test]
+    async fn numeric_equality() {
+        let vm = new_vm(vec![], HashMap::new());
+        let run = |code: &str| {
+            let code = askql_parser::parse(code.to_string(), false).unwrap();
+            vm.run(code, None, None)
+        };
+        let equals =
+            |code: &'static str| async move { run(code).await == Ok(Value::Boolean(true)) };
+        assert!(equals("call(get('equals'), 1, 1.0, call(get('+'), 0.5, 0.5))").await);
+        assert!(
+            equals("call(get('equals'), 18446744073709551616, 1.8446744073709551616e19)").await
+        );
+        assert!(!equals("call(get('equals'), 1, 1, 2)").await);
+        assert!(!equals("call(get('equals'), 1, '1')").await);
+    }
+
     #[tokio::test]
     async fn chained_operation() {
         let vm = new_vm(vec![], HashMap::new());
@@ -153,8 +286,110 @@ mod tests {
         assert_eq!(Ok(Value::Float(0.0)), result);
     }
 
+    #[tokio::test]
+    async fn askscript_operation() {
+        let vm = new_vm(vec![], HashMap::new());
+        let ask_script = "ask {
+            const x = 2 + 3
+            return x * 4 - 1.5
+        }";
+        let code = askql_parser::parse_askscript(ask_script.to_string(), false).unwrap();
+        let result = vm.run(code, None, None).await;
+        assert_eq!(Ok(Value::Float(18.5)), result);
+    }
+
+    // Counts its calls, to show when an expression is evaluated.
+    struct TickResource(std::sync::atomic::AtomicI64);
+
+    #[async_trait::async_trait]
+    impl crate::resource::Resource for TickResource {
+        fn name(&self) -> String {
+            "tick".to_string()
+        }
+        async fn resolver(&self, _args: Vec<Value>) -> Value {
+            Value::Int(self.0.fetch_add(1, std::sync::atomic::Ordering::SeqCst) + 1)
+        }
+    }
+
+    #[tokio::test]
+    async fn bindings() {
+        let mut values = HashMap::new();
+        values.insert(
+            "name".to_string(),
+            AskCodeOrValue::new_value(Value::String("Ana".to_string())),
+        );
+        let vm = new_vm(vec![Box::new(TickResource(Default::default()))], values);
+        let ask_script = "ask {
+            let x = tick()
+            x = x + x
+            x = x + x
+            const list = '!'
+            return [x, tick(), [list], query { name: name:concat(list) }]
+        }";
+        let code = askql_parser::parse_askscript(ask_script.to_string(), false).unwrap();
+        let result = vm.run(code, None, None).await.unwrap();
+        assert_eq!(result.to_string(), r#"[4,2,["!"],{"name":"Ana!"}]"#);
+    }
+
+    #[tokio::test]
+    async fn object_literal() {
+        let vm = new_vm(vec![], HashMap::new());
+        let mut expected = askql_parser::Object::new();
+        expected.insert("name".to_string(), Value::String("x".to_string()));
+        expected.insert("age".to_string(), Value::Int(3));
+        expected.insert("tags".to_string(), Value::List(vec![Value::Int(1)]));
+        let code =
+            askql_parser::parse("{ name: 'x', 'age': 3, tags: [1] }".to_string(), false).unwrap();
+        let result = vm.run(code, None, None).await.unwrap();
+        assert_eq!(result.to_string(), r#"{"name":"x","age":3,"tags":[1]}"#);
+        assert_eq!(result, Value::Object(expected.clone()));
+        let ask_script = "ask { const age = 1 + 2; return { name: 'x', \"age\": age, tags: [1] } }";
+        let code = askql_parser::parse_askscript(ask_script.to_string(), false).unwrap();
+        assert_eq!(vm.run(code, None, None).await, Ok(Value::Object(expected)));
+    }
+
+    #[tokio::test]
+    async fn literal_keywords() {
+        let vm = new_vm(vec![], HashMap::new());
+        let code = askql_parser::parse("ask(list(true, false, null))".to_string(), false).unwrap();
+        let expected = Value::List(vec![
+            Value::Boolean(true),
+            Value::Boolean(false),
+            Value::Null,
+        ]);
+        assert_eq!(vm.run(code, None, None).await, Ok(expected));
+        let mut run_options = RunOptions::new(vec![], HashMap::new());
+        assert!(run_options
+            .register_with_name(SumResource, "true".to_string())
+            .is_some());
+        assert!(run_options
+            .register_with_name(SumResource, "null".to_string())
+            .is_some());
+        assert!(run_options
+            .register_with_name(SumResource, "nullable".to_string())
+            .is_none());
+    }
+
+    #[test]
+    fn reserved_names() {
+        let mut values = HashMap::new();
+        values.insert("null".to_string(), AskCodeOrValue::new_value(Value::Int(0)));
+        values.insert("zero".to_string(), AskCodeOrValue::new_value(Value::Int(0)));
+        let run_options = RunOptions::new(vec![], values);
+        assert_eq!(run_options.values.keys().collect::<Vec<_>>(), vec!["zero"]);
+    }
+
     #[tokio::test]
     async fn complex_test() {
+        #[derive(IntoValue)]
+        struct Friend {
+            id: i64,
+            #[askql(rename = "firstName")]
+            first_name: String,
+            #[askql(rename = "lastName")]
+            last_name: String,
+        }
+
         let mut values = std::collections::HashMap::new();
         values.insert(
             "firstName".to_string(),
@@ -164,51 +399,124 @@ mod tests {
             "lastName".to_string(),
             AskCodeOrValue::new_value(Value::String("SecondName".to_string())),
         );
-        let mut friend0 = std::collections::BTreeMap::new();
-        let mut friend1 = std::collections::BTreeMap::new();
-        let mut friend2 = std::collections::BTreeMap::new();
-        friend0.insert("id".to_string(), Value::Int(1));
-        friend0.insert(
-            "firstName".to_string(),
-            Value::String("Friend 1".to_string()),
-        );
-        friend0.insert(
-            "lastName".to_string(),
-            Value::String("1".to_string())
-        );
-        friend1.insert("id".to_string(), Value::Int(2));
-        friend1.insert(
-            "firstName".to_string(),
-            Value::String("Friend 2".to_string()),
-        );
-        friend1.insert(
-            "lastName".to_string(),
-            Value::String("2".to_string())
-        );
-        friend2.insert("id".to_string(), Value::Int(3));
-        friend2.insert(
-            "firstName".to_string(),
-            Value::String("Friend 3".to_string()),
-        );
-        friend2.insert(
-            "lastName".to_string(),
-            Value::String("3".to_string())
-        );
-        let friends = vec![
-            Value::Object(friend0),
-            Value::Object(friend1),
-            Value::Object(friend2),
-        ];
+        let friends: Vec<Friend> = (1..=3)
+            .map(|id| Friend {
+                id,
+                first_name: format!("Friend {}", id),
+                last_name: id.to_string(),
+            })
+            .collect();
         values.insert(
             "friends".to_string(),
-            AskCodeOrValue::new_value(Value::List(friends)),
+            AskCodeOrValue::new_value(friends.into_value()),
         );
         let vm = new_vm(vec![], values);
         let ask_code = "ask(query(node('firstName',f(call(get('concat'),call(get('toLowerCase'),get('firstName')),' ','is my ','name')))))";
         let code = askql_parser::parse(ask_code.to_string(), false).unwrap();
         let result = vm.run(code, None, None).await;
-        let mut object_result = std::collections::BTreeMap::new();
+        let mut object_result = askql_parser::Object::new();
         object_result.insert("firstName".to_string(), Value::String("primeironome is my name".to_string()));
         assert_eq!(Ok(Value::Object(object_result)), result);
     }
+
+    #[tokio::test]
+    async fn query_field_order() {
+        let mut values = HashMap::new();
+        values.insert(
+            "name".to_string(),
+            AskCodeOrValue::new_value(Value::String("Ana".to_string())),
+        );
+        values.insert("id".to_string(), AskCodeOrValue::new_value(Value::Int(7)));
+        let vm = new_vm(vec![], values);
+        let ask_code = "ask(query(node('name', f(get('name'))), node('id', f(get('id')))))";
+        let code = askql_parser::parse(ask_code.to_string(), false).unwrap();
+        let result = vm.run(code, None, None).await.unwrap();
+        assert_eq!(result.to_string(), r#"{"name":"Ana","id":7}"#);
+    }
+
+    #[tokio::test]
+    async fn comparisons() {
+        let vm = new_vm(vec![], HashMap::new());
+        let ask_script = "ask { return [1 < 2.5, 2 <= 2, 'b' > 'a', 3 >= 4, 1 < '2'] }";
+        let code = askql_parser::parse_askscript(ask_script.to_string(), false).unwrap();
+        let expected = [true, true, true, false, false]
+            .iter()
+            .map(|b| Value::Boolean(*b))
+            .collect();
+        assert_eq!(vm.run(code, None, None).await, Ok(Value::List(expected)));
+    }
+
+    #[tokio::test]
+    async fn logic_and_division() {
+        let vm = new_vm(vec![], HashMap::new());
+        let ask_script = "ask {
+            return [1 != 2, 1 != 1.0, 0 && 'a', 'a' && 'b', null || 'b', !'', !1, 7 / 2, 6 / 3, 7 % 3, -7.5 % 2, 1 / 0, 1 % 0]
+        }";
+        let code = askql_parser::parse_askscript(ask_script.to_string(), false).unwrap();
+        let result = vm.run(code, None, None).await.unwrap();
+        assert_eq!(
+            result.to_string(),
+            r#"[true,false,0,"b","b",true,false,3.5,2,1,-1.5,null,null]"#
+        );
+    }
+
+    #[tokio::test]
+    async fn short_circuit() {
+        let vm = new_vm(
+            vec![Box::new(TickResource(Default::default()))],
+            HashMap::new(),
+        );
+        let ask_script = "ask {
+            return [false && tick(), 'a' || tick(), true && tick(), null || tick()]
+        }";
+        let code = askql_parser::parse_askscript(ask_script.to_string(), false).unwrap();
+        let result = vm.run(code, None, None).await.unwrap();
+        assert_eq!(result.to_string(), r#"[false,"a",1,2]"#);
+        // Called through `call`, the arguments are already evaluated.
+        let code = askql_parser::parse("call(get('and'),0,1)".to_string(), false).unwrap();
+        assert_eq!(vm.run(code, None, None).await, Ok(Value::Int(0)));
+    }
+
+    #[tokio::test]
+    async fn dates() {
+        let mut run_options = new_options(vec![], HashMap::new());
+        let now = askql_parser::parse_date_time("2020-02-01T12:00:00Z").unwrap();
+        run_options.clock = Box::new(crate::clock::FixedClock(now));
+        let vm = AskVm::new(run_options);
+        let ask_script = "ask {
+            const start = date('2020-01-31T22:00:00-03:00')
+            const end = start + duration('P1DT2H')
+            return [toISOString(end), toISOString(end - start), start < end, now() > end, now() - duration(1500)]
+        }";
+        let code = askql_parser::parse_askscript(ask_script.to_string(), false).unwrap();
+        let result = vm.run(code, None, None).await.unwrap();
+        assert_eq!(
+            result.to_string(),
+            r#"["2020-02-02T03:00:00Z","P1DT2H",true,false,"2020-02-01T11:59:58.500Z"]"#
+        );
+    }
+
+    #[tokio::test]
+    async fn bytes() {
+        let vm = new_vm(vec![], HashMap::new());
+        let ask_script = "ask {
+            const data = fromHex('00ff10AB')
+            return [data, byteLength(data), toHex(sliceBytes(data, 1, -1)), toBase64('hi'), fromBase64('aGk='), fromHex('0g')]
+        }";
+        let code = askql_parser::parse_askscript(ask_script.to_string(), false).unwrap();
+        let result = vm.run(code, None, None).await.unwrap();
+        assert_eq!(
+            result.to_string(),
+            r#"["AP8Qqw==",4,"ff10","aGk=","aGk=",null]"#
+        );
+        let code = askql_parser::parse(
+            "ask(call(get('sliceBytes'), call(get('fromHex'), 'aabbcc'), -2))".to_string(),
+            false,
+        )
+        .unwrap();
+        assert_eq!(
+            vm.run(code, None, None).await,
+            Ok(Value::Bytes(vec![0xbb, 0xcc]))
+        );
+    }
 }
@@ -58,23 +58,23 @@ impl Type for &str {
     }
 }
 
-impl Type for i32 {
+impl Type for i64 {
     fn name() -> String {
         "int".to_string()
     }
 
     fn validate<U: Type>(_value: U) -> bool {
-        i32::name() == U::name()
+        i64::name() == U::name()
     }
 }
 
-impl Type for f32 {
+impl Type for f64 {
     fn name() -> String {
         "float".to_string()
     }
 
     fn validate<U: Type>(_value: U) -> bool {
-        f32::name() == U::name()
+        f64::name() == U::name()
     }
 }
 